@@ -0,0 +1,311 @@
+//! Argon2id key derivation function (RFC 9106), version 0x13.
+//!
+//! rust-crypto doesn't implement Argon2. Lanes are filled one after another.
+
+use crypto::blake2b::Blake2b;
+use crypto::digest::Digest;
+use super::Error;
+
+const BLOCK_WORDS: usize = 128;
+const SYNC_POINTS: usize = 4;
+const VERSION: u32 = 0x13;
+const ARGON2ID: u32 = 2;
+/// Maximum memory used by argon2id, in KiB blocks as `m_cost`, same 2 GiB as scrypt.
+const MAX_MEMORY: u32 = 2 << 20;
+
+type Block = [u64; BLOCK_WORDS];
+
+fn blake2b(outlen: usize, inputs: &[&[u8]], output: &mut [u8]) {
+	let mut hasher = Blake2b::new(outlen);
+	for input in inputs {
+		hasher.input(input);
+	}
+	hasher.result(output);
+}
+
+fn le32(value: u32) -> [u8; 4] {
+	[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+/// Variable length hash function H'.
+fn hash_long(inputs: &[&[u8]], output: &mut [u8]) {
+	let len = le32(output.len() as u32);
+	let mut all = vec![&len[..]];
+	all.extend_from_slice(inputs);
+	if output.len() <= 64 {
+		let outlen = output.len();
+		blake2b(outlen, &all, output);
+		return;
+	}
+
+	let mut v = [0u8; 64];
+	blake2b(64, &all, &mut v);
+	output[..32].copy_from_slice(&v[..32]);
+	let mut position = 32;
+	while output.len() - position > 64 {
+		let previous = v;
+		blake2b(64, &[&previous], &mut v);
+		output[position..position + 32].copy_from_slice(&v[..32]);
+		position += 32;
+	}
+	let (previous, outlen) = (v, output.len() - position);
+	blake2b(outlen, &[&previous], &mut output[position..]);
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+	let mut block = [0u64; BLOCK_WORDS];
+	for (i, word) in block.iter_mut().enumerate() {
+		*word = bytes[i * 8..i * 8 + 8].iter().rev().fold(0u64, |word, byte| (word << 8) | *byte as u64);
+	}
+	block
+}
+
+fn mul_lo(a: u64, b: u64) -> u64 {
+	(a & 0xffff_ffff).wrapping_mul(b & 0xffff_ffff)
+}
+
+fn gb(v: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+	v[a] = v[a].wrapping_add(v[b]).wrapping_add(mul_lo(v[a], v[b]).wrapping_mul(2));
+	v[d] = (v[d] ^ v[a]).rotate_right(32);
+	v[c] = v[c].wrapping_add(v[d]).wrapping_add(mul_lo(v[c], v[d]).wrapping_mul(2));
+	v[b] = (v[b] ^ v[c]).rotate_right(24);
+	v[a] = v[a].wrapping_add(v[b]).wrapping_add(mul_lo(v[a], v[b]).wrapping_mul(2));
+	v[d] = (v[d] ^ v[a]).rotate_right(16);
+	v[c] = v[c].wrapping_add(v[d]).wrapping_add(mul_lo(v[c], v[d]).wrapping_mul(2));
+	v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Permutation P of 16 words at given positions of the block.
+fn permute(v: &mut Block, i: &[usize; 16]) {
+	gb(v, i[0], i[4], i[8], i[12]);
+	gb(v, i[1], i[5], i[9], i[13]);
+	gb(v, i[2], i[6], i[10], i[14]);
+	gb(v, i[3], i[7], i[11], i[15]);
+	gb(v, i[0], i[5], i[10], i[15]);
+	gb(v, i[1], i[6], i[11], i[12]);
+	gb(v, i[2], i[7], i[8], i[13]);
+	gb(v, i[3], i[4], i[9], i[14]);
+}
+
+/// Compression function G. Result is xored into `next` if `xor` is set.
+fn fill_block(previous: &Block, reference: &Block, next: &mut Block, xor: bool) {
+	let mut r = [0u64; BLOCK_WORDS];
+	for i in 0..BLOCK_WORDS {
+		r[i] = previous[i] ^ reference[i];
+	}
+	let mut z = r;
+	// rows
+	for i in 0..8 {
+		let mut indices = [0usize; 16];
+		for (j, index) in indices.iter_mut().enumerate() {
+			*index = 16 * i + j;
+		}
+		permute(&mut z, &indices);
+	}
+	// columns
+	for i in 0..8 {
+		let mut indices = [0usize; 16];
+		for (j, index) in indices.iter_mut().enumerate() {
+			*index = 2 * i + (j / 2) * 16 + j % 2;
+		}
+		permute(&mut z, &indices);
+	}
+	for i in 0..BLOCK_WORDS {
+		next[i] = match xor {
+			true => next[i] ^ z[i] ^ r[i],
+			false => z[i] ^ r[i],
+		};
+	}
+}
+
+struct Instance {
+	memory: Vec<Block>,
+	lanes: usize,
+	lane_length: usize,
+	segment_length: usize,
+	passes: u32,
+}
+
+impl Instance {
+	/// Index of the reference block within its lane.
+	fn index_alpha(&self, pass: u32, slice: usize, index: usize, pseudo_rand: u32, same_lane: bool) -> usize {
+		let area = match (pass, same_lane) {
+			(0, _) if slice == 0 => index - 1,
+			(0, true) => slice * self.segment_length + index - 1,
+			(0, false) if index == 0 => slice * self.segment_length - 1,
+			(0, false) => slice * self.segment_length,
+			(_, true) => self.lane_length - self.segment_length + index - 1,
+			(_, false) if index == 0 => self.lane_length - self.segment_length - 1,
+			(_, false) => self.lane_length - self.segment_length,
+		} as u64;
+
+		let relative = (pseudo_rand as u64 * pseudo_rand as u64) >> 32;
+		let relative = area - 1 - ((area * relative) >> 32);
+		let start = match pass == 0 || slice == SYNC_POINTS - 1 {
+			true => 0,
+			false => (slice + 1) * self.segment_length,
+		};
+		(start + relative as usize) % self.lane_length
+	}
+
+	fn fill_segment(&mut self, pass: u32, lane: usize, slice: usize) {
+		// Argon2id uses data independent addressing in the first half of the first pass
+		let independent = pass == 0 && slice < SYNC_POINTS / 2;
+		let zero = [0u64; BLOCK_WORDS];
+		let mut input = [0u64; BLOCK_WORDS];
+		let mut addresses = [0u64; BLOCK_WORDS];
+		input[0] = pass as u64;
+		input[1] = lane as u64;
+		input[2] = slice as u64;
+		input[3] = self.memory.len() as u64;
+		input[4] = self.passes as u64;
+		input[5] = ARGON2ID as u64;
+
+		let next_addresses = |input: &mut Block, addresses: &mut Block| {
+			input[6] += 1;
+			let mut temp = [0u64; BLOCK_WORDS];
+			fill_block(&zero, input, &mut temp, false);
+			fill_block(&zero, &temp, addresses, false);
+		};
+
+		let start = match pass == 0 && slice == 0 {
+			true => {
+				if independent {
+					next_addresses(&mut input, &mut addresses);
+				}
+				2
+			},
+			false => 0,
+		};
+
+		let mut offset = lane * self.lane_length + slice * self.segment_length + start;
+		let mut previous = match offset % self.lane_length {
+			0 => offset + self.lane_length - 1,
+			_ => offset - 1,
+		};
+
+		for i in start..self.segment_length {
+			if offset % self.lane_length == 1 {
+				previous = offset - 1;
+			}
+
+			let pseudo_rand = match independent {
+				true => {
+					if i % BLOCK_WORDS == 0 {
+						next_addresses(&mut input, &mut addresses);
+					}
+					addresses[i % BLOCK_WORDS]
+				},
+				false => self.memory[previous][0],
+			};
+
+			let reference_lane = match pass == 0 && slice == 0 {
+				true => lane,
+				false => (pseudo_rand >> 32) as usize % self.lanes,
+			};
+			let reference_index = self.index_alpha(pass, slice, i, pseudo_rand as u32, reference_lane == lane);
+			let reference = self.memory[reference_lane * self.lane_length + reference_index];
+			let previous_block = self.memory[previous];
+			fill_block(&previous_block, &reference, &mut self.memory[offset], pass != 0);
+
+			offset += 1;
+			previous += 1;
+		}
+	}
+}
+
+fn argon2id_with(password: &[u8], salt: &[u8], secret: &[u8], data: &[u8], m_cost: u32, t_cost: u32, lanes: u32, output: &mut [u8]) -> Result<(), Error> {
+	if lanes == 0 || lanes >= 1 << 24 || t_cost == 0 || (m_cost as u64) < 8 * lanes as u64 || salt.len() < 8 || output.len() < 4 {
+		return Err(Error::Custom("Invalid argon2id parameters".into()));
+	}
+
+	if m_cost > MAX_MEMORY {
+		return Err(Error::Custom("Argon2id parameters exceed memory limit".into()));
+	}
+
+	let mut h0 = [0u8; 64];
+	blake2b(64, &[
+		&le32(lanes), &le32(output.len() as u32), &le32(m_cost), &le32(t_cost), &le32(VERSION), &le32(ARGON2ID),
+		&le32(password.len() as u32), password,
+		&le32(salt.len() as u32), salt,
+		&le32(secret.len() as u32), secret,
+		&le32(data.len() as u32), data,
+	], &mut h0);
+
+	let lanes = lanes as usize;
+	let segment_length = m_cost as usize / (lanes * SYNC_POINTS);
+	let lane_length = segment_length * SYNC_POINTS;
+	let mut instance = Instance {
+		memory: vec![[0u64; BLOCK_WORDS]; lane_length * lanes],
+		lanes: lanes,
+		lane_length: lane_length,
+		segment_length: segment_length,
+		passes: t_cost,
+	};
+
+	let mut bytes = [0u8; BLOCK_WORDS * 8];
+	for lane in 0..lanes {
+		for column in 0..2 {
+			hash_long(&[&h0, &le32(column as u32), &le32(lane as u32)], &mut bytes);
+			instance.memory[lane * lane_length + column] = block_from_bytes(&bytes);
+		}
+	}
+
+	for pass in 0..t_cost {
+		for slice in 0..SYNC_POINTS {
+			for lane in 0..lanes {
+				instance.fill_segment(pass, lane, slice);
+			}
+		}
+	}
+
+	let mut last = instance.memory[lane_length - 1];
+	for lane in 1..lanes {
+		for (word, other) in last.iter_mut().zip(instance.memory[lane * lane_length + lane_length - 1].iter()) {
+			*word ^= *other;
+		}
+	}
+	for (i, word) in last.iter().enumerate() {
+		for j in 0..8 {
+			bytes[i * 8 + j] = (word >> (8 * j)) as u8;
+		}
+	}
+	hash_long(&[&bytes], output);
+	Ok(())
+}
+
+/// Derives `output` from the password with `m_cost` KiB of memory, `t_cost` passes and `lanes` lanes.
+/// Salt must be at least 8 bytes long.
+pub fn argon2id(password: &[u8], salt: &[u8], m_cost: u32, t_cost: u32, lanes: u32, output: &mut [u8]) -> Result<(), Error> {
+	argon2id_with(password, salt, &[], &[], m_cost, t_cost, lanes, output)
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::ToHex;
+	use super::{argon2id, argon2id_with};
+
+	#[test]
+	fn rfc9106_test_vector() {
+		let mut output = [0u8; 32];
+		argon2id_with(&[1u8; 32], &[2u8; 16], &[3u8; 8], &[4u8; 12], 32, 3, 4, &mut output).unwrap();
+		assert_eq!(output.to_hex(), "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659");
+	}
+
+	#[test]
+	fn reference_test_vector() {
+		let mut output = [0u8; 32];
+		argon2id(b"password", b"somesalt", 256, 2, 1, &mut output).unwrap();
+		assert_eq!(output.to_hex(), "9dfeb910e80bad0311fee20f9c0e2b12c17987b4cac90c2ef54d5b3021c68bfe");
+	}
+
+	#[test]
+	fn invalid_parameters() {
+		let mut output = [0u8; 32];
+		assert!(argon2id(b"", b"somesalt", 32, 0, 1, &mut output).is_err());
+		assert!(argon2id(b"", b"somesalt", 31, 1, 4, &mut output).is_err());
+		assert!(argon2id(b"", b"salt", 32, 1, 1, &mut output).is_err());
+		assert!(argon2id(b"", b"somesalt", 32, 1, 1, &mut output).is_ok());
+		assert!(argon2id(b"", b"somesalt", u32::max_value(), 1, 1, &mut output).is_err());
+	}
+}
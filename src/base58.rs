@@ -0,0 +1,124 @@
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+
+const ALPHABET: &'static [u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn checksum(data: &[u8]) -> [u8; 4] {
+	let mut first = [0u8; 32];
+	let mut second = [0u8; 32];
+	let mut sha = Sha256::new();
+	sha.input(data);
+	sha.result(&mut first);
+	sha.reset();
+	sha.input(&first);
+	sha.result(&mut second);
+
+	let mut result = [0u8; 4];
+	result.copy_from_slice(&second[0..4]);
+	result
+}
+
+/// Encodes data as base58 string.
+pub fn to_base58(data: &[u8]) -> String {
+	let zeros = data.iter().take_while(|b| **b == 0).count();
+	// base58 digits, least significant first
+	let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+	for byte in &data[zeros..] {
+		let mut carry = *byte as u32;
+		for digit in digits.iter_mut() {
+			carry += (*digit as u32) << 8;
+			*digit = (carry % 58) as u8;
+			carry /= 58;
+		}
+		while carry > 0 {
+			digits.push((carry % 58) as u8);
+			carry /= 58;
+		}
+	}
+
+	let mut result = String::with_capacity(zeros + digits.len());
+	for _ in 0..zeros {
+		result.push(ALPHABET[0] as char);
+	}
+	for digit in digits.iter().rev() {
+		result.push(ALPHABET[*digit as usize] as char);
+	}
+	result
+}
+
+/// Decodes base58 string. Returns `None` if string contains invalid characters.
+pub fn from_base58(s: &str) -> Option<Vec<u8>> {
+	let zeros = s.bytes().take_while(|b| *b == ALPHABET[0]).count();
+	// bytes, least significant first
+	let mut bytes: Vec<u8> = Vec::with_capacity(s.len() * 733 / 1000 + 1);
+	for c in s.bytes().skip(zeros) {
+		let mut carry = match ALPHABET.iter().position(|a| *a == c) {
+			Some(position) => position as u32,
+			None => return None,
+		};
+		for byte in bytes.iter_mut() {
+			carry += (*byte as u32) * 58;
+			*byte = carry as u8;
+			carry >>= 8;
+		}
+		while carry > 0 {
+			bytes.push(carry as u8);
+			carry >>= 8;
+		}
+	}
+
+	let mut result = vec![0u8; zeros];
+	result.extend(bytes.iter().rev());
+	Some(result)
+}
+
+/// Encodes data followed by 4 bytes double sha256 checksum.
+pub fn to_base58_check(data: &[u8]) -> String {
+	let mut bytes = data.to_vec();
+	bytes.extend_from_slice(&checksum(data));
+	to_base58(&bytes)
+}
+
+/// Decodes base58 string and validates its checksum.
+pub fn from_base58_check(s: &str) -> Option<Vec<u8>> {
+	let mut bytes = match from_base58(s) {
+		Some(bytes) => bytes,
+		None => return None,
+	};
+
+	if bytes.len() < 4 {
+		return None;
+	}
+
+	let data_len = bytes.len() - 4;
+	if checksum(&bytes[..data_len]) != bytes[data_len..] {
+		return None;
+	}
+
+	bytes.truncate(data_len);
+	Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::FromHex;
+	use super::{to_base58, from_base58, to_base58_check, from_base58_check};
+
+	#[test]
+	fn base58_roundtrip() {
+		let data = "00000a0b0c".from_hex().unwrap();
+		let encoded = to_base58(&data);
+		assert_eq!(encoded, "114Nf5");
+		assert_eq!(from_base58(&encoded).unwrap(), data);
+	}
+
+	#[test]
+	fn base58_check() {
+		let data = "00f54a5851e9372b87810a8e60cdd2e7cfd80b6e31".from_hex().unwrap();
+		let encoded = to_base58_check(&data);
+		assert_eq!(encoded, "1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAs");
+		assert_eq!(from_base58_check(&encoded).unwrap(), data);
+		assert!(from_base58_check("1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAt").is_none());
+		assert!(from_base58("0OIl").is_none());
+	}
+}
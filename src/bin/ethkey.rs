@@ -1,13 +1,21 @@
 extern crate docopt;
+extern crate num_cpus;
 extern crate rustc_serialize;
 extern crate ethkey;
 
 use std::str::FromStr;
-use std::{env, fmt, process};
+use std::{env, fmt, process, io};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 use std::num::ParseIntError;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
 use docopt::Docopt;
-use rustc_serialize::hex::{FromHex, FromHexError};
-use ethkey::{KeyPair, Random, Brain, Prefix, Error as EthkeyError, Generator, Secret, Message, Public, Signature, Address, sign, verify_public, verify_address};
+use rustc_serialize::hex::{FromHex, FromHexError, ToHex};
+use ethkey::{KeyPair, Random, BrainKdf, HardenedBrain, BrainPhrase, BrainPrefix, BrainRecovery, Prefix, Vanity, Deployer, Scoring, Score, SecretTemplate, SecretRecovery, VanityWork, Matcher, Mnemonic, DerivationPath, KeyFile, Kdf, Checkpoint, SearchSeed, Progress, Error as EthkeyError, Generator, Secret, Message, Public, Signature, Address, TypedData, Transaction, SaltMiner, SaltTarget, Keccak256, sign, verify_public, verify_address, random_phrase, encrypt, decrypt, personal_message, sign_typed_data, recover_typed_data, public_to_address, contract_address, create2_address, recover_sender, vanity_combine};
 
 pub const USAGE: &'static str = r#"
 Ethereum keys generator.
@@ -15,12 +23,37 @@ Ethereum keys generator.
 
 Usage:
     ethkey info <secret> [options]
+    ethkey info --mnemonic PHRASE [options]
     ethkey generate random [options]
     ethkey generate prefix <prefix> <iterations> [options]
+    ethkey generate prefix --resume FILE [options]
     ethkey generate brain <seed> [options]
-    ethkey sign <secret> <message>
-    ethkey verify public <public> <signature> <message>
-    ethkey verify address <address> <signature> <message>
+    ethkey generate brain-phrase [options]
+    ethkey generate brain-prefix <prefix> <iterations> [options]
+    ethkey generate mnemonic [options]
+    ethkey generate vanity --pattern PATTERN [options]
+    ethkey generate deployer <pattern> [options]
+    ethkey generate zeros [options]
+    ethkey keystore encrypt <secret> <password> [options]
+    ethkey keystore decrypt <file> <password> [options]
+    ethkey keystore inspect <file>
+    ethkey encrypt <public> <data>
+    ethkey decrypt <secret> <ciphertext>
+    ethkey sign [--personal] <secret> <message>
+    ethkey verify public [--personal] <public> <signature> <message>
+    ethkey verify address [--personal] <address> <signature> <message>
+    ethkey sign-typed <secret> <file>
+    ethkey verify-typed <address> <signature> <file>
+    ethkey sign-tx <secret> <file>
+    ethkey tx-sender <raw>
+    ethkey vanity-request
+    ethkey vanity-work <public> --pattern PATTERN [options]
+    ethkey vanity-combine <secret> <offset> [options]
+    ethkey contract-address <address> <nonce>
+    ethkey contract-address <address> --salt SALT (--init-code CODE | --init-code-hash HASH)
+    ethkey recover-brain <address> <phrase> [options]
+    ethkey recover-secret <template> <address> [options]
+    ethkey mine-salt <address> (--init-code CODE | --init-code-hash HASH) (--pattern PATTERN | --zero-bytes BYTES) [options]
     ethkey [-h | --help]
 
 Options:
@@ -28,6 +61,53 @@ Options:
     -s, --secret       Display only the secret.
     -p, --public       Display only the public.
     -a, --address      Display only the address.
+    --mnemonic PHRASE  Use BIP39 mnemonic phrase instead of the secret.
+    --passphrase PASS  BIP39 passphrase [default: ].
+    --path PATH        BIP32 derivation path [default: m/44'/60'/0'/0/0].
+    --words WORDS      Number of words of generated mnemonic or brain phrase
+                       [default: 12].
+    --kdf KDF          Key derivation function. Key file: scrypt (default) or
+                       pbkdf2. Brain: keccak (default), keccak:ROUNDS,
+                       scrypt[:N:R:P], pbkdf2[:ITERATIONS] or
+                       argon2id[:MEMORY_KIB:PASSES:LANES].
+    --personal         Sign or verify EIP-191 personal message given as text,
+                       0x prefixed hex or @file, instead of 32 bytes hash.
+    --threads THREADS  Number of search and recovery threads, defaults to
+                       number of CPUs.
+    --checkpoint FILE  Save prefix search state to the file, so that it can
+                       be resumed.
+    --resume FILE      Resume prefix search from the state file. Uses its
+                       number of threads.
+    --checkpoint-interval SECONDS
+                       Seconds between prefix search state saves [default: 60].
+    --password PASS    Password encrypting the secret of prefix search state.
+    --plaintext-checkpoint
+                       Store the secret of prefix search state unencrypted.
+    --pattern PATTERN  Vanity address pattern. Hex prefix (dead), prefix and
+                       suffix (dead...beef), contains:HEX or regex:REGEX.
+                       ? matches any nibble, uppercase letters must match
+                       EIP-55 checksum case.
+    --iterations ITERATIONS
+                       Maximum number of vanity search tries, unlimited by
+                       default.
+    --salt SALT        CREATE2 salt, up to 32 bytes hex, left padded with
+                       zeros. Brain salt text, eg. an email.
+    --init-code CODE   CREATE2 contract init code hex.
+    --init-code-hash HASH
+                       CREATE2 keccak256 hash of contract init code.
+    --nonce NONCE      Nonce of the deployment transaction [default: 0].
+    --score SCORE      Rank addresses by leading or total zero bytes
+                       [default: leading].
+    --target SCORE     Stop zero bytes search when address reaches the score,
+                       20 by default.
+    --time SECONDS     Stop search after given number of seconds, unlimited
+                       by default.
+    --distance DISTANCE
+                       Maximal number of edits of mistyped brain phrase,
+                       at most 3 [default: 2].
+    --zero-bytes BYTES
+                       Minimal number of leading zero bytes of mined
+                       contract address.
 
 Commands:
     info               Display public and address of the secret.
@@ -35,8 +115,40 @@ Commands:
     random             Random generation.
     prefix             Random generation, but address must start with a prefix
     brain              Generate new key from string seed.
+    brain-phrase       Generate new brain key from random phrase of brain
+                       wordlist words, which are not BIP39 words.
+    brain-prefix       Random brain phrase generation, but address must start
+                       with a prefix.
+    mnemonic           Generate new BIP39 mnemonic phrase and its key.
+    vanity             Random generation, but address must match a pattern.
+    deployer           Random generation, but address of contract deployed
+                       with CREATE at given nonce must match a pattern.
+    zeros              Search for address with the most zero bytes.
+    keystore           Manage Web3 Secret Storage (v3) key files.
+    keystore encrypt   Encrypt secret with a password into key file json.
+    keystore decrypt   Decrypt secret from key file with a password.
+    keystore inspect   Display key file metadata without decrypting it.
+    encrypt            Encrypt data to the public using ECIES.
+    decrypt            Decrypt ECIES encrypted data using secret.
     sign               Sign message using secret.
     verify             Verify signer of the signature.
+    sign-typed         Sign EIP-712 typed data json file using secret.
+    verify-typed       Verify signer of the EIP-712 typed data signature.
+    sign-tx            Sign transaction json file using secret.
+    tx-sender          Display sender, chain id, nonce and hash of raw signed
+                       transaction.
+    vanity-request     Generate keypair for split-key vanity search. Keep the
+                       secret, share the public with the worker.
+    vanity-work        Search for secret offset, which gives address matching
+                       the pattern when added to the public's secret.
+    vanity-combine     Combine requester's secret with worker's offset.
+    contract-address   Display address of contract deployed by the address
+                       with CREATE at given nonce or with CREATE2.
+    recover-brain      Recover brain phrase of the address from mistyped one.
+    recover-secret     Recover secret of the address from hex with unknown
+                       characters marked by ?.
+    mine-salt          Search for CREATE2 salt, which gives contract address
+                       matching the pattern or starting with zero bytes.
 "#;
 
 #[derive(Debug, RustcDecodable)]
@@ -46,10 +158,31 @@ struct Args {
 	cmd_random: bool,
 	cmd_prefix: bool,
 	cmd_brain: bool,
+	cmd_brain_phrase: bool,
+	cmd_brain_prefix: bool,
+	cmd_mnemonic: bool,
+	cmd_vanity: bool,
+	cmd_deployer: bool,
+	cmd_zeros: bool,
+	cmd_keystore: bool,
+	cmd_encrypt: bool,
+	cmd_decrypt: bool,
+	cmd_inspect: bool,
 	cmd_sign: bool,
 	cmd_verify: bool,
 	cmd_public: bool,
 	cmd_address: bool,
+	cmd_sign_typed: bool,
+	cmd_verify_typed: bool,
+	cmd_sign_tx: bool,
+	cmd_tx_sender: bool,
+	cmd_vanity_request: bool,
+	cmd_vanity_work: bool,
+	cmd_vanity_combine: bool,
+	cmd_contract_address: bool,
+	cmd_mine_salt: bool,
+	cmd_recover_brain: bool,
+	cmd_recover_secret: bool,
 	arg_prefix: String,
 	arg_iterations: String,
 	arg_seed: String,
@@ -58,9 +191,42 @@ struct Args {
 	arg_public: String,
 	arg_address: String,
 	arg_signature: String,
+	arg_password: String,
+	arg_file: String,
+	arg_data: String,
+	arg_ciphertext: String,
+	arg_raw: String,
+	arg_offset: String,
+	arg_nonce: String,
+	arg_pattern: String,
+	arg_phrase: String,
+	arg_template: String,
 	flag_secret: bool,
 	flag_public: bool,
 	flag_address: bool,
+	flag_mnemonic: String,
+	flag_passphrase: String,
+	flag_path: String,
+	flag_words: String,
+	flag_kdf: String,
+	flag_personal: bool,
+	flag_threads: String,
+	flag_checkpoint: String,
+	flag_resume: String,
+	flag_checkpoint_interval: String,
+	flag_password: String,
+	flag_plaintext_checkpoint: bool,
+	flag_pattern: String,
+	flag_iterations: String,
+	flag_salt: String,
+	flag_init_code: String,
+	flag_init_code_hash: String,
+	flag_nonce: String,
+	flag_score: String,
+	flag_target: String,
+	flag_time: String,
+	flag_distance: String,
+	flag_zero_bytes: String,
 }
 
 #[derive(Debug)]
@@ -68,6 +234,7 @@ enum Error {
 	Ethkey(EthkeyError),
 	FromHex(FromHexError),
 	ParseInt(ParseIntError),
+	Io(io::Error),
 }
 
 impl From<EthkeyError> for Error {
@@ -88,12 +255,19 @@ impl From<ParseIntError> for Error {
 	}
 }
 
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
 impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
 		match *self {
 			Error::Ethkey(ref e) => write!(f, "{}", e),
 			Error::FromHex(ref e) => write!(f, "{}", e),
 			Error::ParseInt(ref e) => write!(f, "{}", e),
+			Error::Io(ref e) => write!(f, "{}", e),
 		}
 	}
 }
@@ -134,7 +308,215 @@ fn display(keypair: KeyPair, mode: DisplayMode) -> String {
 		DisplayMode::KeyPair => format!("{}", keypair),
 		DisplayMode::Secret => format!("{}", keypair.secret()),
 		DisplayMode::Public => format!("{}", keypair.public()),
-		DisplayMode::Address => format!("{}", keypair.address()),
+		DisplayMode::Address => keypair.address().to_checksum(),
+	}
+}
+
+fn read_file(path: &str) -> Result<String, Error> {
+	let mut content = String::new();
+	try!(try!(File::open(path)).read_to_string(&mut content));
+	Ok(content)
+}
+
+fn read_key_file(path: &str) -> Result<KeyFile, Error> {
+	Ok(try!(KeyFile::from_str(&try!(read_file(path)))))
+}
+
+fn read_typed_data(path: &str) -> Result<TypedData, Error> {
+	Ok(try!(TypedData::from_str(&try!(read_file(path)))))
+}
+
+fn read_transaction(path: &str) -> Result<Transaction, Error> {
+	Ok(try!(Transaction::from_str(&try!(read_file(path)))))
+}
+
+fn kdf(args: &Args) -> Result<Kdf, Error> {
+	match args.flag_kdf.as_ref() {
+		"" | "scrypt" => Ok(try!(Kdf::scrypt(262144, 8, 1))),
+		"pbkdf2" => Ok(try!(Kdf::pbkdf2(262144))),
+		_ => Err(EthkeyError::Custom(format!("Unknown key derivation function: {}", args.flag_kdf)).into()),
+	}
+}
+
+/// Writes checkpoint to temporary file first, so that interruption doesn't corrupt the previous one.
+fn write_checkpoint(path: &str, checkpoint: &Checkpoint) -> Result<(), EthkeyError> {
+	let temp = format!("{}.tmp", path);
+	// permissions are set only when the file is created
+	let _ = fs::remove_file(&temp);
+	let mut options = OpenOptions::new();
+	options.write(true).create_new(true);
+	owner_only(&mut options);
+	try!(try!(options.open(&temp)).write_all(checkpoint.to_string().as_bytes()));
+	try!(fs::rename(&temp, path));
+	Ok(())
+}
+
+/// Checkpoint may contain plaintext seed of the search, it must not be readable by other users.
+#[cfg(unix)]
+fn owner_only(options: &mut OpenOptions) {
+	options.mode(0o600);
+}
+
+#[cfg(not(unix))]
+fn owner_only(_options: &mut OpenOptions) {
+}
+
+/// Starts new prefix search or resumes saved one.
+fn resumable_prefix(args: &Args) -> Result<KeyPair, Error> {
+	let password = match args.flag_password.is_empty() {
+		true => None,
+		false => Some(args.flag_password.as_str()),
+	};
+
+	let (path, checkpoint) = if args.flag_resume.is_empty() {
+		let seed = try!(Random.generate()).secret().clone();
+		let stored = if args.flag_plaintext_checkpoint {
+			SearchSeed::Plain(seed)
+		} else if let Some(password) = password {
+			SearchSeed::Encrypted(try!(KeyFile::encrypt(&seed, password, try!(kdf(args)))))
+		} else {
+			return Err(EthkeyError::Custom("Checkpoint secret must be encrypted, use --password or --plaintext-checkpoint".into()).into());
+		};
+		let prefix = try!(args.arg_prefix.from_hex());
+		let iterations = try!(usize::from_str_radix(&args.arg_iterations, 10));
+		let checkpoint = Checkpoint::new(prefix, iterations, try!(threads(args)), stored);
+		try!(write_checkpoint(&args.flag_checkpoint, &checkpoint));
+		(&args.flag_checkpoint, checkpoint)
+	} else {
+		(&args.flag_resume, try!(Checkpoint::from_str(&try!(read_file(&args.flag_resume)))))
+	};
+
+	let seed = try!(checkpoint.seed.secret(password));
+	let interval = Duration::from_secs(try!(u64::from_str_radix(&args.flag_checkpoint_interval, 10)));
+	let progress = try!(progress(args));
+	Ok(try!(with_status(&progress, Some(checkpoint.difficulty()), || Prefix::resume(checkpoint, &seed, interval, &progress, |checkpoint| write_checkpoint(path, checkpoint)))))
+}
+
+fn progress(args: &Args) -> Result<Progress, Error> {
+	match args.flag_time.is_empty() {
+		true => Ok(Progress::new()),
+		false => Ok(Progress::with_timeout(Duration::from_secs(try!(u64::from_str_radix(&args.flag_time, 10))))),
+	}
+}
+
+fn format_duration(duration: Duration) -> String {
+	let seconds = duration.as_secs();
+	match seconds {
+		0..=59 => format!("{}s", seconds),
+		60..=3599 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+		3600..=86399 => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+		_ => format!("{}d {:02}h", seconds / 86400, seconds % 86400 / 3600),
+	}
+}
+
+fn format_count(count: f64) -> String {
+	match count < 1e12 {
+		true => format!("{:.0}", count),
+		false => format!("{:.2e}", count),
+	}
+}
+
+fn status(progress: &Progress, difficulty: Option<f64>) -> String {
+	let status = format!("{} attempts, {:.0}/s, {} elapsed", progress.attempts(), progress.rate(), format_duration(progress.elapsed()));
+	match difficulty {
+		Some(difficulty) => {
+			let eta = progress.eta(difficulty).map(format_duration).unwrap_or_else(|| "unknown".into());
+			format!("{}, {:.1}% probability, expected in {}", status, progress.probability(difficulty) * 100.0, eta)
+		},
+		None => status,
+	}
+}
+
+fn exhaustive_status(progress: &Progress, size: f64) -> String {
+	let remaining = progress.remaining(size).map(format_duration).unwrap_or_else(|| "unknown".into());
+	format!("{}, {:.1}% searched, all tried in {}", status(progress, None), progress.searched(size) * 100.0, remaining)
+}
+
+/// Displays difficulty and live search status on stderr while `search` runs.
+fn with_status<T, F>(progress: &Progress, difficulty: Option<f64>, search: F) -> T where F: FnOnce() -> T {
+	let _ = match difficulty {
+		Some(difficulty) => writeln!(io::stderr(), "difficulty: {} attempts", format_count(difficulty)),
+		None => writeln!(io::stderr(), "difficulty: unknown"),
+	};
+	with_status_line(progress, move |progress| status(progress, difficulty), search)
+}
+
+/// Like `with_status`, for exhaustive search over `size` candidates, which finds the match
+/// in `size / 2` attempts on average and in at most `size` attempts.
+fn with_exhaustive_status<T, F>(progress: &Progress, size: f64, search: F) -> T where F: FnOnce() -> T {
+	let _ = writeln!(io::stderr(), "difficulty: {} attempts on average, {} candidates", format_count(size / 2.0), format_count(size));
+	with_status_line(progress, move |progress| exhaustive_status(progress, size), search)
+}
+
+fn with_status_line<T, F, S>(progress: &Progress, status: S, search: F) -> T where F: FnOnce() -> T, S: Fn(&Progress) -> String + Send + 'static {
+	let (tx, rx) = mpsc::channel::<()>();
+	let status_progress = progress.clone();
+	let status_line = thread::spawn(move || {
+		let mut printed = false;
+		while let Err(mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(Duration::from_secs(1)) {
+			// trailing spaces overwrite the rest of a longer previous line
+			let _ = write!(io::stderr(), "\r{}    ", status(&status_progress));
+			printed = true;
+		}
+		if printed {
+			let _ = writeln!(io::stderr());
+		}
+	});
+
+	let result = search();
+	drop(tx);
+	let _ = status_line.join();
+	result
+}
+
+fn threads(args: &Args) -> Result<usize, Error> {
+	match args.flag_threads.is_empty() {
+		true => Ok(num_cpus::get()),
+		false => Ok(try!(usize::from_str_radix(&args.flag_threads, 10))),
+	}
+}
+
+fn iterations(args: &Args) -> Result<usize, Error> {
+	match args.flag_iterations.is_empty() {
+		true => Ok(usize::max_value()),
+		false => Ok(try!(usize::from_str_radix(&args.flag_iterations, 10))),
+	}
+}
+
+/// Reads hex with optional 0x prefix.
+fn hex(data: &str) -> Result<Vec<u8>, Error> {
+	let data = if data.starts_with("0x") { &data[2..] } else { data };
+	Ok(try!(data.from_hex()))
+}
+
+/// Reads at most 32 bytes hex, left padded with zeros.
+fn hash32(data: &str) -> Result<[u8; 32], Error> {
+	let bytes = try!(hex(data));
+	if bytes.len() > 32 {
+		return Err(EthkeyError::Custom(format!("Expected at most 32 bytes: {}", data)).into());
+	}
+	let mut result = [0u8; 32];
+	result[32 - bytes.len()..].copy_from_slice(&bytes);
+	Ok(result)
+}
+
+fn init_code_hash(args: &Args) -> Result<[u8; 32], Error> {
+	match args.flag_init_code_hash.is_empty() {
+		true => Ok(try!(hex(&args.flag_init_code)).keccak256()),
+		false => hash32(&args.flag_init_code_hash),
+	}
+}
+
+/// Reads personal message given as text, 0x prefixed hex or @file.
+fn personal_data(data: &str) -> Result<Vec<u8>, Error> {
+	if data.starts_with('@') {
+		let mut result = Vec::new();
+		try!(try!(File::open(&data[1..])).read_to_end(&mut result));
+		Ok(result)
+	} else if data.starts_with("0x") {
+		Ok(try!(data[2..].from_hex()))
+	} else {
+		Ok(data.as_bytes().to_vec())
 	}
 }
 
@@ -145,31 +527,152 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 
 	return if args.cmd_info {
 		let display_mode = DisplayMode::new(&args);
-		let secret = try!(Secret::from_str(&args.arg_secret));
-		let keypair = try!(KeyPair::from_secret(secret));
+		let keypair = if args.flag_mnemonic.is_empty() {
+			let secret = try!(Secret::from_str(&args.arg_secret));
+			try!(KeyPair::from_secret(secret))
+		} else {
+			let path = try!(DerivationPath::from_str(&args.flag_path));
+			try!(Mnemonic::with_path(args.flag_mnemonic, args.flag_passphrase, path).generate())
+		};
 		Ok(display(keypair, display_mode))
+	} else if args.cmd_generate && args.cmd_mnemonic {
+		let display_mode = DisplayMode::new(&args);
+		let words = try!(usize::from_str_radix(&args.flag_words, 10));
+		let phrase = try!(random_phrase(words));
+		let path = try!(DerivationPath::from_str(&args.flag_path));
+		let keypair = try!(Mnemonic::with_path(phrase.clone(), args.flag_passphrase, path).generate());
+		Ok(match display_mode {
+			DisplayMode::KeyPair => format!("phrase:  {}\n{}", phrase, display(keypair, display_mode)),
+			_ => display(keypair, display_mode),
+		})
+	} else if args.cmd_generate && args.cmd_brain_phrase {
+		let display_mode = DisplayMode::new(&args);
+		let words = try!(usize::from_str_radix(&args.flag_words, 10));
+		let brain = try!(BrainPhrase::random(words));
+		let (phrase, entropy) = (brain.phrase().to_owned(), brain.entropy());
+		let keypair = try!(brain.generate());
+		Ok(match display_mode {
+			DisplayMode::KeyPair => format!("phrase:  {}\nentropy: {:.1} bits\n{}", phrase, entropy, display(keypair, display_mode)),
+			_ => display(keypair, display_mode),
+		})
+	} else if args.cmd_generate && args.cmd_brain_prefix {
+		let display_mode = DisplayMode::new(&args);
+		let prefix = try!(args.arg_prefix.from_hex());
+		let words = try!(usize::from_str_radix(&args.flag_words, 10));
+		let iterations = try!(usize::from_str_radix(&args.arg_iterations, 10));
+		let progress = try!(progress(&args));
+		let brain = BrainPrefix::with_progress(prefix, words, iterations, try!(threads(&args)), progress.clone());
+		let (phrase, keypair) = try!(with_status(&progress, Some(brain.difficulty()), || brain.search()));
+		Ok(match display_mode {
+			DisplayMode::KeyPair => format!("phrase:  {}\n{}", phrase, display(keypair, display_mode)),
+			_ => display(keypair, display_mode),
+		})
+	} else if args.cmd_generate && args.cmd_deployer {
+		let display_mode = DisplayMode::new(&args);
+		let matcher = try!(Matcher::from_str(&args.arg_pattern));
+		let nonce = try!(u64::from_str_radix(&args.flag_nonce, 10));
+		let progress = try!(progress(&args));
+		let difficulty = matcher.difficulty();
+		let deployer = Deployer::with_progress(matcher, nonce, try!(iterations(&args)), try!(threads(&args)), progress.clone());
+		let keypair = try!(with_status(&progress, difficulty, || deployer.generate()));
+		let contract = contract_address(&keypair.address(), nonce);
+		Ok(match display_mode {
+			DisplayMode::KeyPair => format!("{}\ncontract: {}", display(keypair, display_mode), contract.to_checksum()),
+			_ => display(keypair, display_mode),
+		})
+	} else if args.cmd_generate && args.cmd_zeros {
+		let display_mode = DisplayMode::new(&args);
+		let score = try!(Score::from_str(&args.flag_score));
+		let target = match args.flag_target.is_empty() {
+			true => 20,
+			false => try!(usize::from_str_radix(&args.flag_target, 10)),
+		};
+		let progress = try!(progress(&args));
+		let scoring = Scoring::with_progress(score, target, try!(threads(&args)), progress.clone());
+		// stream each new best, so that it's not lost if the search is interrupted
+		let best = try!(with_status(&progress, Some(scoring.difficulty()), || scoring.search(|keypair, score| {
+			// padding overwrites the status line
+			let _ = writeln!(io::stderr(), "\r{:<100}\n{}\n", format!("score:   {}", score), keypair);
+		})));
+		match best {
+			Some((keypair, score)) => Ok(match display_mode {
+				DisplayMode::KeyPair => format!("score:   {}\n{}", score, display(keypair, display_mode)),
+				_ => display(keypair, display_mode),
+			}),
+			None if progress.is_cancelled() => Err(EthkeyError::Cancelled.into()),
+			None => Err(EthkeyError::Custom("Could not find keypair".into()).into()),
+		}
 	} else if args.cmd_generate {
 		let display_mode = DisplayMode::new(&args);
 		let keypair = if args.cmd_random {
 			Random.generate()
+		} else if args.cmd_prefix && (!args.flag_checkpoint.is_empty() || !args.flag_resume.is_empty()) {
+			return Ok(display(try!(resumable_prefix(&args)), display_mode));
 		} else if args.cmd_prefix {
 			let prefix = try!(args.arg_prefix.from_hex());
 			let iterations = try!(usize::from_str_radix(&args.arg_iterations, 10));
-			Prefix::new(prefix, iterations).generate()
+			let progress = try!(progress(&args));
+			let prefix = Prefix::with_progress(prefix, iterations, try!(threads(&args)), progress.clone());
+			with_status(&progress, Some(prefix.difficulty()), || prefix.generate())
+		} else if args.cmd_vanity {
+			let matcher = try!(Matcher::from_str(&args.flag_pattern));
+			let progress = try!(progress(&args));
+			let difficulty = matcher.difficulty();
+			let vanity = Vanity::with_progress(matcher, try!(iterations(&args)), try!(threads(&args)), progress.clone());
+			with_status(&progress, difficulty, || vanity.generate())
 		} else if args.cmd_brain {
-			Brain::new(args.arg_seed).generate()
+			let kdf = match args.flag_kdf.is_empty() {
+				true => BrainKdf::default(),
+				false => try!(BrainKdf::from_str(&args.flag_kdf)),
+			};
+			HardenedBrain::new(args.arg_seed, args.flag_salt, kdf).generate()
 		} else {
 			unreachable!();
 		};
 		Ok(display(try!(keypair), display_mode))
+	} else if args.cmd_keystore {
+		if args.cmd_encrypt {
+			let secret = try!(Secret::from_str(&args.arg_secret));
+			Ok(format!("{}", try!(KeyFile::encrypt(&secret, &args.arg_password, try!(kdf(&args))))))
+		} else if args.cmd_decrypt {
+			let display_mode = DisplayMode::new(&args);
+			let secret = try!(try!(read_key_file(&args.arg_file)).decrypt(&args.arg_password));
+			Ok(display(try!(KeyPair::from_secret(secret)), display_mode))
+		} else if args.cmd_inspect {
+			let key_file = try!(read_key_file(&args.arg_file));
+			let address = key_file.address.map(|address| address.to_checksum()).unwrap_or_else(|| "unknown".into());
+			Ok(format!("id:      {}\naddress: {}\nkdf:     {}", key_file.id, address, key_file.kdf.name()))
+		} else {
+			unreachable!();
+		}
+	} else if args.cmd_encrypt {
+		let public = try!(Public::from_str(&args.arg_public));
+		let data = try!(args.arg_data.from_hex());
+		Ok(try!(encrypt(&public, &[], &data)).to_hex())
+	} else if args.cmd_decrypt {
+		let secret = try!(Secret::from_str(&args.arg_secret));
+		let ciphertext = try!(args.arg_ciphertext.from_hex());
+		Ok(try!(decrypt(&secret, &[], &ciphertext)).to_hex())
 	} else if args.cmd_sign {
 		let secret = try!(Secret::from_str(&args.arg_secret));
-		let message = try!(Message::from_str(&args.arg_message));
-		let signature = try!(sign(&secret, &message));
-		Ok(format!("{}", signature))
+		if args.flag_personal {
+			let message = personal_message(&try!(personal_data(&args.arg_message)));
+			let signature = try!(sign(&secret, &message));
+			Ok(signature.into_electrum().to_hex())
+		} else {
+			let message = try!(Message::from_str(&args.arg_message));
+			let signature = try!(sign(&secret, &message));
+			Ok(format!("{}", signature))
+		}
 	} else if args.cmd_verify {
-		let signature = try!(Signature::from_str(&args.arg_signature));
-		let message = try!(Message::from_str(&args.arg_message));
+		let mut signature = try!(Signature::from_str(&args.arg_signature));
+		if signature.v() >= 27 {
+			signature = Signature::from_electrum(&signature[..]);
+		}
+		let message = match args.flag_personal {
+			true => personal_message(&try!(personal_data(&args.arg_message))),
+			false => try!(Message::from_str(&args.arg_message)),
+		};
 		let ok = if args.cmd_public {
 			let public = try!(Public::from_str(&args.arg_public));
 			try!(verify_public(&public, &signature, &message))
@@ -180,6 +683,86 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 			unreachable!();
 		};
 		Ok(format!("{}", ok))
+	} else if args.cmd_sign_typed {
+		let secret = try!(Secret::from_str(&args.arg_secret));
+		let typed_data = try!(read_typed_data(&args.arg_file));
+		Ok(try!(sign_typed_data(&secret, &typed_data)).into_electrum().to_hex())
+	} else if args.cmd_verify_typed {
+		let mut signature = try!(Signature::from_str(&args.arg_signature));
+		if signature.v() >= 27 {
+			signature = Signature::from_electrum(&signature[..]);
+		}
+		let address = try!(Address::from_str(&args.arg_address));
+		let typed_data = try!(read_typed_data(&args.arg_file));
+		let ok = match recover_typed_data(&signature, &typed_data) {
+			Ok(public) => public_to_address(&public) == address,
+			Err(EthkeyError::InvalidSignature) => false,
+			Err(err) => return Err(err.into()),
+		};
+		Ok(format!("{}", ok))
+	} else if args.cmd_sign_tx {
+		let secret = try!(Secret::from_str(&args.arg_secret));
+		let signed = try!(try!(read_transaction(&args.arg_file)).sign(&secret));
+		Ok(format!("raw:  {}\nhash: {}", signed.raw().to_hex(), signed.hash()))
+	} else if args.cmd_tx_sender {
+		let (signed, sender) = try!(recover_sender(&try!(hex(&args.arg_raw))));
+		let chain_id = signed.transaction.chain_id.map(|chain_id| chain_id.to_string()).unwrap_or_else(|| "none".into());
+		Ok(format!("sender:   {}\nchain id: {}\nnonce:    {}\nhash:     {}", sender.to_checksum(), chain_id, signed.transaction.nonce, signed.hash()))
+	} else if args.cmd_vanity_request {
+		let keypair = try!(Random.generate());
+		Ok(format!("secret:  {}\npublic:  {}", keypair.secret(), keypair.public()))
+	} else if args.cmd_vanity_work {
+		let public = try!(Public::from_str(&args.arg_public));
+		let matcher = try!(Matcher::from_str(&args.flag_pattern));
+		let progress = try!(progress(&args));
+		let difficulty = matcher.difficulty();
+		let work = VanityWork::with_progress(public, matcher, try!(iterations(&args)), try!(threads(&args)), progress.clone());
+		let (offset, address) = try!(with_status(&progress, difficulty, || work.search()));
+		Ok(format!("offset:  {}\naddress: {}", offset, address.to_checksum()))
+	} else if args.cmd_vanity_combine {
+		let display_mode = DisplayMode::new(&args);
+		let secret = try!(Secret::from_str(&args.arg_secret));
+		let offset = try!(Secret::from_str(&args.arg_offset));
+		Ok(display(try!(vanity_combine(&secret, &offset)), display_mode))
+	} else if args.cmd_contract_address {
+		let address = try!(Address::from_str(&args.arg_address));
+		let contract = if args.flag_salt.is_empty() {
+			let nonce = try!(u64::from_str_radix(&args.arg_nonce, 10));
+			contract_address(&address, nonce)
+		} else {
+			create2_address(&address, &try!(hash32(&args.flag_salt)), &try!(init_code_hash(&args)))
+		};
+		Ok(contract.to_checksum())
+	} else if args.cmd_recover_brain {
+		let display_mode = DisplayMode::new(&args);
+		let address = try!(Address::from_str(&args.arg_address));
+		let distance = try!(usize::from_str_radix(&args.flag_distance, 10));
+		let progress = try!(progress(&args));
+		let recovery = BrainRecovery::with_progress(address, args.arg_phrase.clone(), distance, try!(threads(&args)), progress.clone());
+		// candidates are generated while searching, their number isn't known up front
+		let (phrase, keypair) = try!(with_status(&progress, None, || recovery.recover()));
+		Ok(match display_mode {
+			DisplayMode::KeyPair => format!("phrase:  {}\n{}", phrase, display(keypair, display_mode)),
+			_ => display(keypair, display_mode),
+		})
+	} else if args.cmd_recover_secret {
+		let display_mode = DisplayMode::new(&args);
+		let template = try!(SecretTemplate::from_str(&args.arg_template));
+		let address = try!(Address::from_str(&args.arg_address));
+		let progress = try!(progress(&args));
+		let recovery = SecretRecovery::with_progress(template, address, try!(threads(&args)), progress.clone());
+		Ok(display(try!(with_exhaustive_status(&progress, recovery.size() as f64, || recovery.recover())), display_mode))
+	} else if args.cmd_mine_salt {
+		let deployer = try!(Address::from_str(&args.arg_address));
+		let target = match args.flag_pattern.is_empty() {
+			true => SaltTarget::ZeroBytes(try!(usize::from_str_radix(&args.flag_zero_bytes, 10))),
+			false => SaltTarget::Pattern(try!(Matcher::from_str(&args.flag_pattern))),
+		};
+		let progress = try!(progress(&args));
+		let difficulty = target.difficulty();
+		let miner = SaltMiner::with_progress(deployer, try!(init_code_hash(&args)), target, try!(iterations(&args)), try!(threads(&args)), progress.clone());
+		let (salt, address) = try!(with_status(&progress, difficulty, || miner.mine()));
+		Ok(format!("salt:    {}\naddress: {}", salt.to_hex(), address.to_checksum()))
 	} else {
 		unreachable!();
 	}
@@ -187,7 +770,13 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 
 #[cfg(test)]
 mod tests {
-	use super::execute;
+	use std::env;
+	use std::fs::{self, File};
+	use std::io::{Read, Write};
+	use std::sync::atomic::Ordering;
+	use std::time::Duration;
+	use ethkey::Progress;
+	use super::{execute, status, exhaustive_status, format_count, format_duration};
 
 	#[test]
 	fn info() {
@@ -199,7 +788,7 @@ mod tests {
 		let expected = 
 "secret:  17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55
 public:  689268c0ff57a20cd299fa60d3fb374862aff565b20b5f1767906a99e6e09f3ff04ca2b2a5cd22f62941db103c0356df1a8ed20ce322cab2483db67685afd124
-address: 26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
+address: 26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5".to_owned();
 		assert_eq!(execute(command).unwrap(), expected);
 	}
 
@@ -213,8 +802,26 @@ address: 26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
 		let expected = 
 "secret:  17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55
 public:  689268c0ff57a20cd299fa60d3fb374862aff565b20b5f1767906a99e6e09f3ff04ca2b2a5cd22f62941db103c0356df1a8ed20ce322cab2483db67685afd124
-address: 26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
+address: 26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn hardened_brain() {
+		let command = vec!["ethkey", "generate", "brain", "passwd", "--kdf", "pbkdf2:1", "--salt", "salt", "--secret"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc".to_owned();
 		assert_eq!(execute(command).unwrap(), expected);
+
+		let command = vec!["ethkey", "generate", "brain", "passwd", "--kdf", "bcrypt"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert!(execute(command).is_err());
 	}
 
 	#[test]
@@ -246,7 +853,383 @@ address: 26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
 			.map(Into::into)
 			.collect::<Vec<String>>();
 
-		let expected = "26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
+		let expected = "26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn prefix_threads() {
+		let command = vec!["ethkey", "generate", "prefix", "ff", "1000000", "--threads", "2", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert!(execute(command).unwrap().to_lowercase().starts_with("ff"));
+	}
+
+	#[test]
+	fn prefix_checkpoint() {
+		let path = env::temp_dir().join("ethkey-prefix-checkpoint.json");
+		let path = path.to_str().unwrap();
+
+		// secret of the state must be encrypted unless explicitly allowed
+		let command = vec!["ethkey", "generate", "prefix", "ff", "512", "--checkpoint", path]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert!(execute(command).is_err());
+
+		// 20 bytes prefix is never found, search stops at the iterations cap
+		let command = vec!["ethkey", "generate", "prefix", "ffffffffffffffffffffffffffffffffffffffff", "512", "--checkpoint", path, "--password", "test", "--kdf", "pbkdf2", "--threads", "2"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert!(execute(command).is_err());
+
+		let mut content = String::new();
+		File::open(path).unwrap().read_to_string(&mut content).unwrap();
+		assert!(content.contains("\"seed\""));
+		assert!(!content.contains("\"secret\""));
+		assert!(content.contains("\"offsets\":[256,256]"));
+
+		let command = vec!["ethkey", "generate", "prefix", "--resume", path, "--password", "wrong"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert!(execute(command).is_err());
+
+		let command = vec!["ethkey", "generate", "prefix", "--resume", path, "--password", "test"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert!(execute(command).is_err());
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn prefix_plaintext_checkpoint() {
+		let path = env::temp_dir().join("ethkey-prefix-plaintext-checkpoint.json");
+		let path = path.to_str().unwrap();
+
+		let command = vec!["ethkey", "generate", "prefix", "ff", "1000000", "--checkpoint", path, "--plaintext-checkpoint", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert!(execute(command).unwrap().to_lowercase().starts_with("ff"));
+
+		let mut content = String::new();
+		File::open(path).unwrap().read_to_string(&mut content).unwrap();
+		assert!(content.contains("\"secret\""));
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+		}
+
+		let command = vec!["ethkey", "generate", "prefix", "--resume", path, "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert!(execute(command).unwrap().to_lowercase().starts_with("ff"));
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn vanity() {
+		let command = vec!["ethkey", "generate", "vanity", "--pattern", "a...B", "--threads", "2", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let address = execute(command).unwrap();
+		assert!(address.starts_with('a'));
+		assert!(address.ends_with('B'));
+	}
+
+	#[test]
+	fn vanity_time() {
+		let command = vec!["ethkey", "generate", "vanity", "--pattern", "0000000000000000", "--time", "1"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap_err().to_string(), "Crypto error (Search cancelled)");
+	}
+
+	#[test]
+	fn status_line() {
+		let progress = Progress::new();
+		progress.counter().fetch_add(1000, Ordering::Relaxed);
+		let line = status(&progress, Some(1000.0));
+		assert!(line.starts_with("1000 attempts, "));
+		assert!(line.contains("63.2% probability, expected in "));
+		assert!(!status(&progress, None).contains("probability"));
+		assert!(exhaustive_status(&progress, 4000.0).contains("25.0% searched, all tried in "));
+		assert_eq!(format_count(65536.0), "65536");
+		assert_eq!(format_count(2f64.powi(64)), "1.84e19");
+		assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+		assert_eq!(format_duration(Duration::from_secs(3599)), "59m 59s");
+		assert_eq!(format_duration(Duration::from_secs(7260)), "2h 01m");
+		assert_eq!(format_duration(Duration::from_secs(90000)), "1d 01h");
+	}
+
+	#[test]
+	fn vanity_iterations() {
+		let command = vec!["ethkey", "generate", "vanity", "--pattern", "0000000000", "--iterations", "100"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap_err().to_string(), "Crypto error (Could not find keypair)");
+	}
+
+	#[test]
+	fn deployer() {
+		let command = vec!["ethkey", "generate", "deployer", "ab", "--nonce", "3"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		assert_eq!(result.lines().count(), 4);
+		let secret = result.lines().next().unwrap()["secret:  ".len()..].to_owned();
+		let contract = result.lines().last().unwrap()["contract: ".len()..].to_owned();
+		assert!(contract.to_lowercase().starts_with("ab"));
+
+		let command = vec!["ethkey", "info", &secret, "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		let address = execute(command).unwrap();
+
+		let command = vec!["ethkey", "contract-address", &address, "3"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+		assert_eq!(execute(command).unwrap(), contract);
+	}
+
+	#[test]
+	fn zeros() {
+		let command = vec!["ethkey", "generate", "zeros", "--target", "1"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		assert_eq!(result.lines().count(), 4);
+		assert_eq!(result.lines().next().unwrap(), "score:   1");
+		assert!(result.lines().last().unwrap().starts_with("address: 00"));
+	}
+
+	#[test]
+	fn zeros_time() {
+		let command = vec!["ethkey", "generate", "zeros", "--score", "total", "--time", "1", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let address = execute(command).unwrap();
+		assert!((0..20).any(|i| &address[i * 2..i * 2 + 2] == "00"));
+	}
+
+	#[test]
+	fn split_key_vanity() {
+		let command = vec!["ethkey", "vanity-request"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let request = execute(command).unwrap();
+		let request = request.lines().map(|line| line[9..].to_owned()).collect::<Vec<String>>();
+
+		let command = vec!["ethkey", "vanity-work", &request[1], "--pattern", "ab", "--threads", "2"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let work = execute(command).unwrap();
+		let work = work.lines().map(|line| line[9..].to_owned()).collect::<Vec<String>>();
+		assert!(work[1].to_lowercase().starts_with("ab"));
+
+		let command = vec!["ethkey", "vanity-combine", &request[0], &work[0], "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), work[1]);
+	}
+
+	#[test]
+	fn info_mnemonic() {
+		let command = vec!["ethkey", "info", "--mnemonic", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "9858EfFD232B4033E47d90003D41EC34EcaEda94".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn info_mnemonic_path() {
+		let command = vec!["ethkey", "info", "--mnemonic", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about", "--path", "m/44'/60'/0'/0/1", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "6Fac4D18c912343BF86fa7049364Dd4E424Ab9C0".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn generate_mnemonic() {
+		let command = vec!["ethkey", "generate", "mnemonic", "--words", "24"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		let phrase = result.lines().next().unwrap();
+		assert!(phrase.starts_with("phrase:  "));
+		assert_eq!(phrase.split(' ').filter(|word| !word.is_empty()).count(), 25);
+		assert_eq!(result.lines().count(), 4);
+	}
+
+	#[test]
+	fn generate_brain_phrase() {
+		let command = vec!["ethkey", "generate", "brain-phrase", "--words", "8"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		let mut lines = result.lines();
+		let phrase = lines.next().unwrap()["phrase:  ".len()..].to_owned();
+		assert_eq!(phrase.split(' ').count(), 8);
+		assert_eq!(lines.next().unwrap(), "entropy: 103.1 bits");
+		let secret = lines.next().unwrap().to_owned();
+
+		let command = vec!["ethkey", "generate", "brain", &phrase]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap().lines().next().unwrap(), secret);
+	}
+
+	#[test]
+	fn generate_brain_prefix() {
+		let command = vec!["ethkey", "generate", "brain-prefix", "", "1", "--words", "3", "--threads", "1"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		let mut lines = result.lines();
+		let phrase = lines.next().unwrap()["phrase:  ".len()..].to_owned();
+		assert_eq!(phrase.split(' ').count(), 3);
+		let secret = lines.next().unwrap().to_owned();
+
+		let command = vec!["ethkey", "generate", "brain", &phrase]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap().lines().next().unwrap(), secret);
+	}
+
+	#[test]
+	fn recover_brain() {
+		let command = vec!["ethkey", "recover-brain", "26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5", "This is sparta", "--distance", "1"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected =
+"phrase:  this is sparta
+secret:  17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55
+public:  689268c0ff57a20cd299fa60d3fb374862aff565b20b5f1767906a99e6e09f3ff04ca2b2a5cd22f62941db103c0356df1a8ed20ce322cab2483db67685afd124
+address: 26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5".to_owned();
+
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn recover_brain_distance() {
+		// hundreds of thousands of candidates at this distance, the search starts before all of them are known
+		let command = vec!["ethkey", "recover-brain", "26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5", "this is  sparta", "--distance", "3"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		assert_eq!(result.lines().next().unwrap(), "phrase:  this is sparta");
+	}
+
+	#[test]
+	fn recover_secret() {
+		let command = vec!["ethkey", "recover-secret", "17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0b??5", "26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5", "--secret"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn keystore() {
+		let command = vec!["ethkey", "keystore", "encrypt", "17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55", "password", "--kdf", "pbkdf2"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let json = execute(command).unwrap();
+		let path = env::temp_dir().join("ethkey-keystore-test.json");
+		File::create(&path).unwrap().write_all(json.as_bytes()).unwrap();
+		let path = path.to_str().unwrap();
+
+		let command = vec!["ethkey", "keystore", "decrypt", path, "password", "--address"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+
+		let command = vec!["ethkey", "keystore", "decrypt", path, "wrong password"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap_err().to_string(), "Crypto error (Invalid password)");
+
+		let command = vec!["ethkey", "keystore", "inspect", path]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		assert!(result.contains("address: 26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5\nkdf:     pbkdf2"));
+	}
+
+	#[test]
+	fn encrypt_and_decrypt() {
+		let command = vec!["ethkey", "encrypt", "689268c0ff57a20cd299fa60d3fb374862aff565b20b5f1767906a99e6e09f3ff04ca2b2a5cd22f62941db103c0356df1a8ed20ce322cab2483db67685afd124", "0102030405"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let ciphertext = execute(command).unwrap();
+		assert_eq!(ciphertext.len(), (5 + 113) * 2);
+
+		let command = vec!["ethkey", "decrypt", "17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55", &ciphertext]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "0102030405".to_owned();
 		assert_eq!(execute(command).unwrap(), expected);
 	}
 
@@ -261,6 +1244,227 @@ address: 26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
 		assert_eq!(execute(command).unwrap(), expected);
 	}
 
+	#[test]
+	fn sign_personal() {
+		let command = vec!["ethkey", "sign", "--personal", "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318", "Some data"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+
+		let command = vec!["ethkey", "sign", "--personal", "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318", "0x536f6d652064617461"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn verify_personal() {
+		let command = vec!["ethkey", "verify", "address", "--personal", "2c7536e3605d9c16a7a3d7b1898e529396a65c23", "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c", "Some data"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "true".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+
+		let command = vec!["ethkey", "verify", "address", "--personal", "2c7536e3605d9c16a7a3d7b1898e529396a65c23", "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c", "Other data"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "false".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	const MAIL: &'static str = r#"{
+		"types": {
+			"EIP712Domain": [
+				{ "name": "name", "type": "string" },
+				{ "name": "version", "type": "string" },
+				{ "name": "chainId", "type": "uint256" },
+				{ "name": "verifyingContract", "type": "address" }
+			],
+			"Person": [
+				{ "name": "name", "type": "string" },
+				{ "name": "wallet", "type": "address" }
+			],
+			"Mail": [
+				{ "name": "from", "type": "Person" },
+				{ "name": "to", "type": "Person" },
+				{ "name": "contents", "type": "string" }
+			]
+		},
+		"primaryType": "Mail",
+		"domain": {
+			"name": "Ether Mail",
+			"version": "1",
+			"chainId": 1,
+			"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+		},
+		"message": {
+			"from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+			"to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+			"contents": "Hello, Bob!"
+		}
+	}"#;
+
+	#[test]
+	fn sign_and_verify_typed() {
+		let path = env::temp_dir().join("ethkey-typed-data-test.json");
+		File::create(&path).unwrap().write_all(MAIL.as_bytes()).unwrap();
+		let path = path.to_str().unwrap();
+
+		// keccak256("cow")
+		let command = vec!["ethkey", "sign-typed", "c85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4", path]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected = "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+
+		let command = vec!["ethkey", "verify-typed", "cd2a3d9f938e13cd947ec05abc7fe734df8dd826", &expected, path]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), "true".to_owned());
+
+		let command = vec!["ethkey", "verify-typed", "26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5", &expected, path]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), "false".to_owned());
+	}
+
+	#[test]
+	fn sign_tx() {
+		let tx = r#"{
+			"nonce": 9,
+			"gasPrice": "20000000000",
+			"gas": 21000,
+			"to": "0x3535353535353535353535353535353535353535",
+			"value": "1000000000000000000",
+			"chainId": 1
+		}"#;
+		let path = env::temp_dir().join("ethkey-sign-tx-test.json");
+		File::create(&path).unwrap().write_all(tx.as_bytes()).unwrap();
+
+		let command = vec!["ethkey", "sign-tx", "4646464646464646464646464646464646464646464646464646464646464646", path.to_str().unwrap()]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected =
+"raw:  f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83
+hash: 33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn sign_dynamic_fee_tx() {
+		let tx = r#"{
+			"type": 2,
+			"nonce": 0,
+			"maxPriorityFeePerGas": "1000000000",
+			"maxFeePerGas": "30000000000",
+			"gas": 21000,
+			"to": "0x3535353535353535353535353535353535353535",
+			"value": "1000000000000000000",
+			"chainId": 1
+		}"#;
+		let path = env::temp_dir().join("ethkey-sign-dynamic-fee-tx-test.json");
+		File::create(&path).unwrap().write_all(tx.as_bytes()).unwrap();
+
+		let command = vec!["ethkey", "sign-tx", "4646464646464646464646464646464646464646464646464646464646464646", path.to_str().unwrap()]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		assert!(result.starts_with("raw:  02f8"));
+		assert_eq!(result.lines().count(), 2);
+	}
+
+	#[test]
+	fn tx_sender() {
+		let command = vec!["ethkey", "tx-sender", "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let expected =
+"sender:   9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F
+chain id: 1
+nonce:    9
+hash:     33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788".to_owned();
+		assert_eq!(execute(command).unwrap(), expected);
+	}
+
+	#[test]
+	fn contract_address() {
+		let command = vec!["ethkey", "contract-address", "0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0", "1"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), "343c43A37D37dfF08AE8C4A11544c718AbB4fCF8".to_owned());
+	}
+
+	#[test]
+	fn create2_address() {
+		let command = vec!["ethkey", "contract-address", "0x00000000000000000000000000000000deadbeef", "--salt", "0xcafebabe", "--init-code", "0xdeadbeef"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), "60f3f640a8508fC6a86d45DF051962668E1e8AC7".to_owned());
+
+		let command = vec!["ethkey", "contract-address", "0x00000000000000000000000000000000deadbeef", "--salt", "0xcafebabe", "--init-code-hash", "d4fd4e189132273036449fc9e11198c739161b4c0116a9a2dccdfa1c492006f1"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), "60f3f640a8508fC6a86d45DF051962668E1e8AC7".to_owned());
+	}
+
+	#[test]
+	fn mine_salt() {
+		let command = vec!["ethkey", "mine-salt", "0x00000000000000000000000000000000deadbeef", "--init-code", "0xdeadbeef", "--zero-bytes", "1", "--threads", "2"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let result = execute(command).unwrap();
+		let mut lines = result.lines();
+		let salt = lines.next().unwrap()["salt:    ".len()..].to_owned();
+		let address = lines.next().unwrap()["address: ".len()..].to_owned();
+		assert!(address.starts_with("00"));
+
+		let command = vec!["ethkey", "contract-address", "0x00000000000000000000000000000000deadbeef", "--salt", &salt, "--init-code", "0xdeadbeef"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert_eq!(execute(command).unwrap(), address);
+	}
+
+	#[test]
+	fn mine_salt_iterations() {
+		let command = vec!["ethkey", "mine-salt", "0x00000000000000000000000000000000deadbeef", "--init-code-hash", "d4fd4e189132273036449fc9e11198c739161b4c0116a9a2dccdfa1c492006f1", "--pattern", "000000000000", "--iterations", "1000"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		assert!(execute(command).is_err());
+	}
+
 	#[test]
 	fn verify_valid_public() {
 		let command = vec!["ethkey", "verify", "public", "689268c0ff57a20cd299fa60d3fb374862aff565b20b5f1767906a99e6e09f3ff04ca2b2a5cd22f62941db103c0356df1a8ed20ce322cab2483db67685afd124", "c1878cf60417151c766a712653d26ef350c8c75393458b7a9be715f053215af63dfd3b02c2ae65a8677917a8efa3172acb71cb90196e42106953ea0363c5aaf200", "bd50b7370c3f96733b31744c6c45079e7ae6c8d299613246d28ebcef507ec987"]
@@ -1,4 +1,12 @@
+use std::str::FromStr;
+use rand::Rng;
+use rand::os::OsRng;
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha256;
 use keccak::Keccak256;
+use scrypt::scrypt;
+use argon2::argon2id;
 use super::{KeyPair, Error, Generator, Secret};
 
 /// Simple brainwallet.
@@ -12,29 +20,186 @@ impl Brain {
 
 impl Generator for Brain {
 	fn generate(self) -> Result<KeyPair, Error> {
-		let seed = self.0;
-		let mut secret = seed.bytes().collect::<Vec<u8>>().keccak256();
-
-		let mut i = 0;
-		loop {
-			secret = secret.keccak256();
-			
-			match i > 16384 {
-				false => i += 1,
-				true => {
-					let result = KeyPair::from_secret(Secret::from(secret.clone()));
-					if result.is_ok() {
-						return result
-					}
-				},
-			}
+		keccak_brain(self.0.as_bytes(), BRAIN_ROUNDS)
+	}
+}
+
+/// Number of keccak rounds of simple brainwallet.
+const BRAIN_ROUNDS: u32 = 16384;
+
+fn keccak_brain(seed: &[u8], rounds: u32) -> Result<KeyPair, Error> {
+	let mut secret = seed.keccak256();
+
+	// `i` reaches `rounds + 1`, which overflows u32 for `u32::MAX` rounds
+	let rounds = rounds as u64;
+	let mut i = 0u64;
+	loop {
+		secret = secret.keccak256();
+		
+		match i > rounds {
+			false => i += 1,
+			true => {
+				let result = KeyPair::from_secret(Secret::from(secret.clone()));
+				if result.is_ok() {
+					return result
+				}
+			},
 		}
 	}
 }
 
+/// Key derivation function of hardened brainwallet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrainKdf {
+	/// Repeated keccak256 of the phrase, as in simple brainwallet. Non-empty salt follows it after 0x01 byte.
+	Keccak {
+		rounds: u32,
+	},
+	/// Scrypt.
+	Scrypt {
+		/// CPU/memory cost, must be a power of 2.
+		n: u32,
+		/// Block size.
+		r: u32,
+		/// Parallelization.
+		p: u32,
+	},
+	/// PBKDF2 with HMAC-SHA256.
+	Pbkdf2 {
+		/// Number of iterations.
+		c: u32,
+	},
+	/// Argon2id. Salt must be at least 8 bytes long.
+	Argon2id {
+		/// Memory in KiB.
+		m: u32,
+		/// Number of passes.
+		t: u32,
+		/// Number of lanes.
+		p: u32,
+	},
+}
+
+impl Default for BrainKdf {
+	/// Derivation of simple brainwallet.
+	fn default() -> Self {
+		BrainKdf::Keccak { rounds: BRAIN_ROUNDS }
+	}
+}
+
+impl FromStr for BrainKdf {
+	type Err = Error;
+
+	/// Parses name followed by optional colon separated parameters, eg. `keccak:100000`,
+	/// `scrypt:262144:8:1`, `pbkdf2:262144` or `argon2id:65536:3:4`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.split(':');
+		let name = parts.next().unwrap_or("");
+		let params = try!(parts.map(|param| u32::from_str(param).map_err(|_| Error::Custom(format!("Invalid key derivation parameter: {}", param)))).collect::<Result<Vec<_>, _>>());
+		let kdf = match (name, params.len()) {
+			("keccak", 0) => BrainKdf::default(),
+			("keccak", 1) => BrainKdf::Keccak { rounds: params[0] },
+			("scrypt", 0) => BrainKdf::Scrypt { n: 262144, r: 8, p: 1 },
+			("scrypt", 3) => BrainKdf::Scrypt { n: params[0], r: params[1], p: params[2] },
+			("pbkdf2", 0) => BrainKdf::Pbkdf2 { c: 262144 },
+			("pbkdf2", 1) => BrainKdf::Pbkdf2 { c: params[0] },
+			("argon2id", 0) => BrainKdf::Argon2id { m: 65536, t: 3, p: 4 },
+			("argon2id", 3) => BrainKdf::Argon2id { m: params[0], t: params[1], p: params[2] },
+			_ => return Err(Error::Custom(format!("Unknown key derivation function: {}", s))),
+		};
+		Ok(kdf)
+	}
+}
+
+/// Brainwallet with configurable key derivation function and salt, eg. an email.
+/// With default derivation and empty salt it's the same as simple brainwallet.
+pub struct HardenedBrain {
+	phrase: String,
+	salt: String,
+	kdf: BrainKdf,
+}
+
+impl HardenedBrain {
+	pub fn new(phrase: String, salt: String, kdf: BrainKdf) -> Self {
+		HardenedBrain {
+			phrase: phrase,
+			salt: salt,
+			kdf: kdf,
+		}
+	}
+}
+
+impl Generator for HardenedBrain {
+	fn generate(self) -> Result<KeyPair, Error> {
+		let (phrase, salt) = (self.phrase.as_bytes(), self.salt.as_bytes());
+		let mut secret = [0u8; 32];
+		match self.kdf {
+			BrainKdf::Keccak { rounds } if salt.is_empty() => return keccak_brain(phrase, rounds),
+			// separated, so that moving characters between phrase and salt changes the key
+			BrainKdf::Keccak { rounds } => return keccak_brain(&[phrase, &[1], salt].concat(), rounds),
+			BrainKdf::Scrypt { n, r, p } => try!(scrypt(phrase, salt, n, r, p, &mut secret)),
+			BrainKdf::Pbkdf2 { c } => {
+				if c == 0 {
+					return Err(Error::Custom("Invalid pbkdf2 parameters".into()));
+				}
+				let mut mac = Hmac::new(Sha256::new(), phrase);
+				pbkdf2(&mut mac, salt, c, &mut secret);
+			},
+			BrainKdf::Argon2id { m, t, p } => try!(argon2id(phrase, salt, m, t, p, &mut secret)),
+		}
+		KeyPair::from_secret(Secret::from(secret))
+	}
+}
+
+lazy_static! {
+	/// Wordlist of brain phrases. It shares no words with BIP39 wordlist, so phrases can't be mistaken for mnemonics.
+	pub static ref BRAIN_WORDS: Vec<&'static str> = include_str!("../res/brain-words.txt").lines().collect();
+}
+
+/// Brainwallet with phrase of words drawn uniformly from the brain wordlist.
+pub struct BrainPhrase {
+	phrase: String,
+	words: usize,
+}
+
+impl BrainPhrase {
+	/// Draws new phrase with given number of words using OS randomness.
+	pub fn random(words: usize) -> Result<Self, Error> {
+		if words == 0 {
+			return Err(Error::Custom("Brain phrase must have at least one word".into()));
+		}
+
+		let mut rng = try!(OsRng::new());
+		let phrase = (0..words).map(|_| BRAIN_WORDS[rng.gen_range(0, BRAIN_WORDS.len())]).collect::<Vec<_>>().join(" ");
+		Ok(BrainPhrase {
+			phrase: phrase,
+			words: words,
+		})
+	}
+
+	pub fn phrase(&self) -> &str {
+		&self.phrase
+	}
+
+	/// Entropy of the phrase in bits.
+	pub fn entropy(&self) -> f64 {
+		self.words as f64 * (BRAIN_WORDS.len() as f64).log2()
+	}
+}
+
+impl Generator for BrainPhrase {
+	fn generate(self) -> Result<KeyPair, Error> {
+		Brain::new(self.phrase).generate()
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use {Brain, Generator};
+	use std::str::FromStr;
+	use rustc_serialize::hex::ToHex;
+	use {Brain, BrainPhrase, BrainKdf, HardenedBrain, Generator};
+	use mnemonic::ENGLISH;
+	use super::BRAIN_WORDS;
 
 	#[test]
 	fn test_brain() {
@@ -43,4 +208,58 @@ mod tests {
 		let second_keypair = Brain(words.clone()).generate().unwrap();
 		assert_eq!(first_keypair.secret(), second_keypair.secret());
 	}
+
+	#[test]
+	fn brain_phrase() {
+		let phrase = BrainPhrase::random(12).unwrap();
+		assert_eq!(BRAIN_WORDS.len(), 7545);
+		assert_eq!(phrase.entropy(), 12.0 * 7545f64.log2());
+		assert_eq!(phrase.phrase().split(' ').count(), 12);
+		assert!(phrase.phrase().split(' ').all(|word| BRAIN_WORDS.contains(&word) && !ENGLISH.contains(&word)));
+
+		let brain = Brain::new(phrase.phrase().to_owned()).generate().unwrap();
+		assert_eq!(phrase.generate().unwrap().secret(), brain.secret());
+		assert!(BrainPhrase::random(0).is_err());
+	}
+
+	#[test]
+	fn hardened_brain_default() {
+		let brain = Brain::new("this is sparta".into()).generate().unwrap();
+		let hardened = HardenedBrain::new("this is sparta".into(), "".into(), BrainKdf::default()).generate().unwrap();
+		assert_eq!(hardened.secret(), brain.secret());
+
+		let salted = HardenedBrain::new("this is sparta".into(), "sparta@example.com".into(), BrainKdf::default()).generate().unwrap();
+		assert!(salted.secret() != brain.secret());
+		let joined = HardenedBrain::new("this is spartasparta@example.com".into(), "".into(), BrainKdf::default()).generate().unwrap();
+		assert!(salted.secret() != joined.secret());
+	}
+
+	#[test]
+	fn hardened_brain_kdfs() {
+		// first 32 bytes of RFC 7914 scrypt and PBKDF2-HMAC-SHA256 test vectors
+		let scrypt = HardenedBrain::new("".into(), "".into(), BrainKdf::Scrypt { n: 16, r: 1, p: 1 }).generate().unwrap();
+		assert_eq!(scrypt.secret().to_hex(), "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442");
+		let pbkdf2 = HardenedBrain::new("passwd".into(), "salt".into(), BrainKdf::Pbkdf2 { c: 1 }).generate().unwrap();
+		assert_eq!(pbkdf2.secret().to_hex(), "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc");
+
+		let argon2 = HardenedBrain::new("this is sparta".into(), "sparta@example.com".into(), BrainKdf::Argon2id { m: 64, t: 1, p: 2 }).generate().unwrap();
+		assert_eq!(argon2.secret().to_hex(), "dcb8e275db56e20184977a4e0a7021f692d57932782a53382ec61a44e0dbfaed");
+		let keccak = HardenedBrain::new("this is sparta".into(), "sparta@example.com".into(), BrainKdf::Keccak { rounds: 16384 }).generate().unwrap();
+		assert_eq!(keccak.secret().to_hex(), "585c316aa0eff23170bf8a429c5252b72d230472a464bc46e175ee0d5be82b91");
+		assert!(HardenedBrain::new("this is sparta".into(), "short".into(), BrainKdf::Argon2id { m: 64, t: 1, p: 2 }).generate().is_err());
+		assert!(HardenedBrain::new("this is sparta".into(), "".into(), BrainKdf::Pbkdf2 { c: 0 }).generate().is_err());
+	}
+
+	#[test]
+	fn brain_kdf_from_str() {
+		assert_eq!(BrainKdf::from_str("keccak").unwrap(), BrainKdf::default());
+		assert_eq!(BrainKdf::from_str("keccak:100").unwrap(), BrainKdf::Keccak { rounds: 100 });
+		assert_eq!(BrainKdf::from_str("keccak:4294967295").unwrap(), BrainKdf::Keccak { rounds: u32::max_value() });
+		assert_eq!(BrainKdf::from_str("scrypt:1024:8:1").unwrap(), BrainKdf::Scrypt { n: 1024, r: 8, p: 1 });
+		assert_eq!(BrainKdf::from_str("pbkdf2").unwrap(), BrainKdf::Pbkdf2 { c: 262144 });
+		assert_eq!(BrainKdf::from_str("argon2id").unwrap(), BrainKdf::Argon2id { m: 65536, t: 3, p: 4 });
+		assert!(BrainKdf::from_str("scrypt:1024").is_err());
+		assert!(BrainKdf::from_str("pbkdf2:many").is_err());
+		assert!(BrainKdf::from_str("bcrypt").is_err());
+	}
 }
@@ -0,0 +1,98 @@
+//! Search for memorable brain wallet with address starting with a prefix.
+
+use std::cmp;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use incremental::run_workers;
+use prefix::prefix_difficulty;
+use super::{Brain, BrainPhrase, Generator, Progress, KeyPair, Error};
+
+/// Draws random wordlist brain phrases until address of one of them starts with given prefix.
+pub struct BrainPrefix {
+	prefix: Vec<u8>,
+	words: usize,
+	iterations: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl BrainPrefix {
+	pub fn new(prefix: Vec<u8>, words: usize, iterations: usize) -> Self {
+		BrainPrefix::with_threads(prefix, words, iterations, 1)
+	}
+
+	pub fn with_threads(prefix: Vec<u8>, words: usize, iterations: usize, threads: usize) -> Self {
+		BrainPrefix::with_progress(prefix, words, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(prefix: Vec<u8>, words: usize, iterations: usize, threads: usize, progress: Progress) -> Self {
+		BrainPrefix {
+			prefix: prefix,
+			words: words,
+			iterations: iterations,
+			threads: cmp::max(threads, 1),
+			progress: progress,
+		}
+	}
+
+	/// Returns expected number of phrases to try to find matching address.
+	pub fn difficulty(&self) -> f64 {
+		prefix_difficulty(&self.prefix)
+	}
+
+	/// Returns the phrase and its keypair.
+	pub fn search(self) -> Result<(String, KeyPair), Error> {
+		// fail early instead of in every worker
+		try!(BrainPhrase::random(self.words));
+
+		let (prefix, words, iterations, progress) = (self.prefix, self.words, self.iterations, self.progress.clone());
+		let attempts = AtomicUsize::new(0);
+		let found = try!(run_workers(self.threads, &self.progress, move |done| search(&prefix, words, iterations, done, &attempts, &progress)));
+		found.ok_or_else(|| Error::Custom("Could not find keypair".into()))
+	}
+}
+
+/// Draws phrases until address starts with `prefix`, `done` is set, `progress` is cancelled or `attempts` reach `iterations`.
+fn search(prefix: &[u8], words: usize, iterations: usize, done: &AtomicBool, attempts: &AtomicUsize, progress: &Progress) -> Option<Result<(String, KeyPair), Error>> {
+	while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+		if attempts.fetch_add(1, Ordering::Relaxed) >= iterations {
+			return None;
+		}
+
+		let result = BrainPhrase::random(words).and_then(|brain| {
+			let phrase = brain.phrase().to_owned();
+			Brain::new(phrase.clone()).generate().map(|keypair| (phrase, keypair))
+		});
+		progress.counter().fetch_add(1, Ordering::Relaxed);
+		match result {
+			Ok((_, ref keypair)) if !keypair.address().starts_with(prefix) => (),
+			result => return Some(result),
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use {Brain, BrainPrefix, Generator, Progress};
+	use brain::BRAIN_WORDS;
+
+	#[test]
+	fn brain_prefix() {
+		// brain wallets are slow to derive, empty prefix matches the first phrase
+		let progress = Progress::new();
+		let (phrase, keypair) = BrainPrefix::with_progress(vec![], 4, usize::max_value(), 2, progress.clone()).search().unwrap();
+		assert_eq!(phrase.split(' ').count(), 4);
+		assert!(phrase.split(' ').all(|word| BRAIN_WORDS.contains(&word)));
+		assert_eq!(Brain::new(phrase).generate().unwrap().secret(), keypair.secret());
+		assert!(progress.attempts() > 0);
+	}
+
+	#[test]
+	fn brain_prefix_iterations_cap() {
+		let progress = Progress::new();
+		assert!(BrainPrefix::with_progress(vec![0xff; 20], 12, 8, 2, progress.clone()).search().is_err());
+		assert_eq!(progress.attempts(), 8);
+		assert!(BrainPrefix::new(vec![], 0, 8).search().is_err());
+	}
+}
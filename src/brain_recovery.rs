@@ -0,0 +1,316 @@
+//! Brain wallet recovery from a mistyped phrase.
+
+use std::{cmp, mem};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use incremental::run_workers;
+use brain::BRAIN_WORDS;
+use mnemonic::ENGLISH;
+use super::{Brain, Generator, Progress, KeyPair, Address, Error};
+
+/// Maximal edit distance between mistyped word and wordlist word replacing it.
+const NEAREST_DISTANCE: usize = 2;
+
+/// Maximal edit distance of candidate phrases, their number grows exponentially with it.
+///
+/// Every candidate is kept in memory until the search ends, to skip duplicates. A 12-word phrase
+/// has about 10^5 candidates at distance 2 and several millions at distance 3, taking up to a few
+/// hundred megabytes.
+pub const MAX_DISTANCE: usize = 3;
+
+fn levenshtein(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut previous = (0..b.len() + 1).collect::<Vec<_>>();
+	let mut current = vec![0; b.len() + 1];
+	for (i, ca) in a.chars().enumerate() {
+		current[0] = i + 1;
+		for (j, cb) in b.iter().enumerate() {
+			let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+			current[j + 1] = cmp::min(substitution, cmp::min(previous[j + 1], current[j]) + 1);
+		}
+		::std::mem::swap(&mut previous, &mut current);
+	}
+	previous[b.len()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Case {
+	AsTyped,
+	Lower,
+	Capitalized,
+	Upper,
+}
+
+fn render(words: &[String], case: Case) -> String {
+	let phrase = words.join(" ");
+	match case {
+		Case::AsTyped => phrase,
+		Case::Lower => phrase.to_lowercase(),
+		Case::Upper => phrase.to_uppercase(),
+		Case::Capitalized => {
+			let lower = phrase.to_lowercase();
+			let mut chars = lower.chars();
+			match chars.next() {
+				Some(first) => first.to_uppercase().chain(chars).collect(),
+				None => lower,
+			}
+		},
+	}
+}
+
+/// Brain and BIP39 wordlist words close to the word, but different from it.
+fn nearest<'a>(word: &str, cache: &'a mut HashMap<String, Vec<&'static str>>) -> &'a [&'static str] {
+	cache.entry(word.to_owned()).or_insert_with(|| {
+		let lower = word.to_lowercase();
+		BRAIN_WORDS.iter().chain(ENGLISH.iter()).cloned().filter(|candidate| *candidate != lower && levenshtein(&lower, candidate) <= NEAREST_DISTANCE).collect()
+	})
+}
+
+/// Phrases one word substitution, drop or adjacent swap away.
+fn word_edits(words: &[String], cache: &mut HashMap<String, Vec<&'static str>>) -> Vec<Vec<String>> {
+	let mut result = Vec::new();
+	for i in 0..words.len() {
+		for word in nearest(&words[i], cache) {
+			let mut edited = words.to_vec();
+			edited[i] = (*word).to_owned();
+			result.push(edited);
+		}
+
+		if words.len() > 1 {
+			let mut edited = words.to_vec();
+			edited.remove(i);
+			result.push(edited);
+		}
+
+		if i + 1 < words.len() && words[i] != words[i + 1] {
+			let mut edited = words.to_vec();
+			edited.swap(i, i + 1);
+			result.push(edited);
+		}
+	}
+	result
+}
+
+/// Candidate phrases up to given edit distance from the phrase, closer ones first, without duplicates.
+/// Generated lazily, level by level, so that workers can start before all of them are known.
+///
+/// Each of these is one edit: whitespace change, case change of the whole phrase, substitution of a word
+/// with a similar wordlist word, dropping a word or swapping two adjacent words.
+pub struct BrainCandidates {
+	distance: usize,
+	/// Edit distance of phrases in `level`.
+	depth: usize,
+	/// Phrases at `depth` edits, whose edits weren't generated yet.
+	level: VecDeque<(Vec<String>, Case)>,
+	/// Phrases at `depth + 1` edits, kept only if they are going to be edited further.
+	next: Vec<(Vec<String>, Case)>,
+	pending: VecDeque<String>,
+	/// Every queued candidate, see `MAX_DISTANCE` for its size.
+	seen: HashSet<String>,
+	cache: HashMap<String, Vec<&'static str>>,
+}
+
+/// Returns candidate phrases up to given edit distance from the phrase. Distance is at most `MAX_DISTANCE`.
+pub fn brain_candidates(phrase: &str, distance: usize) -> Result<BrainCandidates, Error> {
+	if distance > MAX_DISTANCE {
+		return Err(Error::Custom(format!("Brain phrase distance must be at most {}, got {}", MAX_DISTANCE, distance)));
+	}
+
+	let words = phrase.split_whitespace().map(Into::into).collect::<Vec<String>>();
+	let mut candidates = BrainCandidates {
+		distance: distance,
+		depth: 0,
+		level: VecDeque::new(),
+		next: Vec::new(),
+		pending: VecDeque::new(),
+		seen: HashSet::new(),
+		cache: HashMap::new(),
+	};
+
+	candidates.push(phrase.to_owned());
+	candidates.push(render(&words, Case::AsTyped));
+	if distance > 0 {
+		candidates.push(phrase.trim().to_owned());
+		candidates.push(words.join(""));
+		candidates.push(format!("{} ", words.join(" ")));
+		candidates.push(format!("{}\n", words.join(" ")));
+		candidates.level.push_back((words, Case::AsTyped));
+	}
+	Ok(candidates)
+}
+
+impl BrainCandidates {
+	/// Queues the candidate, returns false if it was already queued.
+	fn push(&mut self, candidate: String) -> bool {
+		match self.seen.contains(&candidate) {
+			true => false,
+			false => {
+				self.seen.insert(candidate.clone());
+				self.pending.push_back(candidate);
+				true
+			},
+		}
+	}
+
+	/// Queues phrases one edit away from the state.
+	fn expand(&mut self, words: Vec<String>, case: Case) {
+		let mut edits = Vec::new();
+		if case == Case::AsTyped {
+			for case in &[Case::Lower, Case::Capitalized, Case::Upper] {
+				edits.push((words.clone(), *case));
+			}
+		}
+		for edited in word_edits(&words, &mut self.cache) {
+			edits.push((edited, case));
+		}
+
+		let last = self.depth + 1 == self.distance;
+		for (words, case) in edits {
+			// phrases rendered the same lead to the same edits
+			if self.push(render(&words, case)) && !last {
+				self.next.push((words, case));
+			}
+		}
+	}
+}
+
+impl Iterator for BrainCandidates {
+	type Item = String;
+
+	fn next(&mut self) -> Option<String> {
+		loop {
+			if let Some(candidate) = self.pending.pop_front() {
+				return Some(candidate);
+			}
+
+			match self.level.pop_front() {
+				Some((words, case)) => self.expand(words, case),
+				None if self.next.is_empty() => return None,
+				None => {
+					self.level = mem::replace(&mut self.next, Vec::new()).into_iter().collect();
+					self.depth += 1;
+				},
+			}
+		}
+	}
+}
+
+/// Searches for brain phrase of the address among variants of mistyped phrase, on multiple threads.
+pub struct BrainRecovery {
+	address: Address,
+	phrase: String,
+	distance: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl BrainRecovery {
+	pub fn new(address: Address, phrase: String, distance: usize, threads: usize) -> Self {
+		BrainRecovery::with_progress(address, phrase, distance, threads, Progress::new())
+	}
+
+	pub fn with_progress(address: Address, phrase: String, distance: usize, threads: usize, progress: Progress) -> Self {
+		BrainRecovery {
+			address: address,
+			phrase: phrase,
+			distance: distance,
+			threads: cmp::max(threads, 1),
+			progress: progress,
+		}
+	}
+
+	/// Phrases which are going to be tried.
+	pub fn candidates(&self) -> Result<BrainCandidates, Error> {
+		brain_candidates(&self.phrase, self.distance)
+	}
+
+	/// Returns the recovered phrase and its keypair.
+	pub fn recover(self) -> Result<(String, KeyPair), Error> {
+		let candidates = Mutex::new(try!(self.candidates()));
+		let (address, progress) = (self.address, self.progress.clone());
+		let found = try!(run_workers(self.threads, &self.progress, move |done| search(&candidates, &address, done, &progress)));
+		found.ok_or_else(|| Error::Custom("Could not recover phrase".into()))
+	}
+}
+
+/// Tries candidates taken from `candidates` until address matches, `done` is set, `progress` is cancelled or candidates run out.
+fn search(candidates: &Mutex<BrainCandidates>, address: &Address, done: &AtomicBool, progress: &Progress) -> Option<Result<(String, KeyPair), Error>> {
+	while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+		// generating candidate is cheap compared to deriving its key, so the lock is not contended
+		let phrase = match candidates.lock().ok().and_then(|mut candidates| candidates.next()) {
+			Some(phrase) => phrase,
+			None => return None,
+		};
+
+		let result = Brain::new(phrase.clone()).generate();
+		progress.counter().fetch_add(1, Ordering::Relaxed);
+		match result {
+			Ok(ref keypair) if keypair.address() != *address => (),
+			result => return Some(result.map(|keypair| (phrase, keypair))),
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use {Address, Progress};
+	use super::{BrainRecovery, MAX_DISTANCE, brain_candidates, levenshtein};
+
+	#[test]
+	fn edit_distance() {
+		assert_eq!(levenshtein("tackle", "tackle"), 0);
+		assert_eq!(levenshtein("tackel", "tackle"), 2);
+		assert_eq!(levenshtein("tackl", "tackle"), 1);
+		assert_eq!(levenshtein("", "abc"), 3);
+	}
+
+	#[test]
+	fn candidates() {
+		let candidates = brain_candidates("leave  tackel normal", 1).unwrap().collect::<Vec<_>>();
+		assert_eq!(candidates[0], "leave  tackel normal");
+		assert_eq!(candidates[1], "leave tackel normal");
+		assert!(candidates.contains(&"leave tackle normal".to_owned()));
+		assert!(candidates.contains(&"tackel leave normal".to_owned()));
+		assert!(candidates.contains(&"leave normal".to_owned()));
+		assert!(candidates.contains(&"Leave tackel normal".to_owned()));
+		assert!(candidates.contains(&"leavetackelnormal".to_owned()));
+		assert!(!candidates.contains(&"leave tackle".to_owned()));
+
+		let further = brain_candidates("leave  tackel normal", 2).unwrap().collect::<Vec<_>>();
+		assert!(further.len() > candidates.len());
+		assert_eq!(&further[..candidates.len()], &candidates[..]);
+		assert!(further.contains(&"leave tackle".to_owned()));
+		assert!(further.contains(&"LEAVE TACKLE NORMAL".to_owned()));
+	}
+
+	#[test]
+	fn candidates_distance() {
+		// closest candidates are generated without enumerating the whole distance
+		let phrase = "leave tackle normal father sausage energy renew dry episode blood chase range";
+		assert_eq!(brain_candidates(phrase, MAX_DISTANCE).unwrap().take(100).count(), 100);
+		assert_eq!(brain_candidates(phrase, 0).unwrap().count(), 1);
+		assert!(brain_candidates(phrase, MAX_DISTANCE + 1).is_err());
+		assert!(BrainRecovery::new(Address::default(), phrase.into(), MAX_DISTANCE + 1, 2).recover().is_err());
+	}
+
+	#[test]
+	fn recover_swapped_words() {
+		// brain wallet of "this is sparta"
+		let address = Address::from_str("26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5").unwrap();
+		let progress = Progress::new();
+		let (phrase, keypair) = BrainRecovery::with_progress(address.clone(), "this sparta is".into(), 1, 2, progress.clone()).recover().unwrap();
+		assert_eq!(phrase, "this is sparta");
+		assert_eq!(keypair.address(), address);
+		assert!(progress.attempts() > 0);
+	}
+
+	#[test]
+	fn recover_fails() {
+		let address = Address::from_str("26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5").unwrap();
+		assert!(BrainRecovery::new(address, "that was athens".into(), 0, 2).recover().is_err());
+	}
+}
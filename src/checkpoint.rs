@@ -0,0 +1,162 @@
+//! Persistent state of resumable prefix search.
+
+use std::fmt;
+use std::str::FromStr;
+use std::collections::BTreeMap;
+use rustc_serialize::hex::{ToHex, FromHex};
+use rustc_serialize::json::{Json, ToJson};
+use prefix::prefix_difficulty;
+use super::{KeyFile, Secret, Error};
+
+/// Seed of the search. Any secret found by the search is derived from it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchSeed {
+	/// Seed encrypted with a password.
+	Encrypted(KeyFile),
+	/// Plaintext seed. Anyone who reads the checkpoint can recover the found secret.
+	Plain(Secret),
+}
+
+impl SearchSeed {
+	/// Returns the seed, decrypting it with the password if it's encrypted.
+	pub fn secret(&self, password: Option<&str>) -> Result<Secret, Error> {
+		match *self {
+			SearchSeed::Encrypted(ref key_file) => key_file.decrypt(try!(password.ok_or(Error::InvalidPassword))),
+			SearchSeed::Plain(ref secret) => Ok(secret.clone()),
+		}
+	}
+}
+
+/// Search state, serializable to json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+	/// Address prefix.
+	pub prefix: Vec<u8>,
+	/// Maximum number of tried secrets, including ones tried before.
+	pub iterations: usize,
+	/// Number of secrets tried by each worker.
+	pub offsets: Vec<u64>,
+	pub seed: SearchSeed,
+}
+
+impl Checkpoint {
+	/// Creates state of a new search with given number of workers.
+	pub fn new(prefix: Vec<u8>, iterations: usize, threads: usize, seed: SearchSeed) -> Self {
+		Checkpoint {
+			prefix: prefix,
+			iterations: iterations,
+			offsets: vec![0; ::std::cmp::max(threads, 1)],
+			seed: seed,
+		}
+	}
+
+	/// Returns expected number of attempts to find matching address.
+	pub fn difficulty(&self) -> f64 {
+		prefix_difficulty(&self.prefix)
+	}
+
+	/// Number of secrets tried so far.
+	pub fn attempts(&self) -> u64 {
+		self.offsets.iter().fold(0u64, |sum, offset| sum.saturating_add(*offset))
+	}
+}
+
+impl ToJson for Checkpoint {
+	fn to_json(&self) -> Json {
+		let mut json = BTreeMap::new();
+		json.insert("version".to_owned(), 1.to_json());
+		json.insert("prefix".to_owned(), self.prefix.to_hex().to_json());
+		json.insert("iterations".to_owned(), (self.iterations as u64).to_json());
+		json.insert("offsets".to_owned(), self.offsets.to_json());
+		match self.seed {
+			SearchSeed::Encrypted(ref key_file) => json.insert("seed".to_owned(), key_file.to_json()),
+			SearchSeed::Plain(ref secret) => json.insert("secret".to_owned(), secret.to_hex().to_json()),
+		};
+		Json::Object(json)
+	}
+}
+
+impl fmt::Display for Checkpoint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{}", self.to_json())
+	}
+}
+
+impl FromStr for Checkpoint {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let json = try!(Json::from_str(s).map_err(|_| Error::InvalidCheckpoint));
+		if json.find("version").and_then(Json::as_u64) != Some(1) {
+			return Err(Error::InvalidCheckpoint);
+		}
+
+		let prefix = try!(json.find("prefix").and_then(Json::as_string).and_then(|s| s.from_hex().ok()).ok_or(Error::InvalidCheckpoint));
+		let iterations = try!(json.find("iterations").and_then(Json::as_u64).ok_or(Error::InvalidCheckpoint));
+		let offsets = try!(json.find("offsets").and_then(Json::as_array).ok_or(Error::InvalidCheckpoint));
+		let offsets = try!(offsets.iter().map(|offset| offset.as_u64().ok_or(Error::InvalidCheckpoint)).collect::<Result<Vec<_>, _>>());
+		if offsets.is_empty() {
+			return Err(Error::InvalidCheckpoint);
+		}
+
+		let seed = match (json.find("seed"), json.find("secret").and_then(Json::as_string)) {
+			(Some(seed), None) => SearchSeed::Encrypted(try!(KeyFile::from_str(&seed.to_string()))),
+			(None, Some(secret)) => SearchSeed::Plain(try!(Secret::from_str(secret))),
+			_ => return Err(Error::InvalidCheckpoint),
+		};
+
+		Ok(Checkpoint {
+			prefix: prefix,
+			iterations: if iterations > usize::max_value() as u64 { usize::max_value() } else { iterations as usize },
+			offsets: offsets,
+			seed: seed,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use {Generator, Random, KeyFile, Kdf, Error};
+	use super::{Checkpoint, SearchSeed};
+
+	#[test]
+	fn plain_checkpoint_roundtrip() {
+		let secret = Random.generate().unwrap().secret().clone();
+		let mut checkpoint = Checkpoint::new(vec![0xab, 0xcd], usize::max_value(), 2, SearchSeed::Plain(secret.clone()));
+		checkpoint.offsets = vec![1024, 768];
+
+		let json = checkpoint.to_string();
+		assert!(json.contains(&format!("\"secret\":\"{}\"", secret)));
+		let restored = Checkpoint::from_str(&json).unwrap();
+		assert_eq!(restored, checkpoint);
+		assert_eq!(restored.attempts(), 1792);
+		assert_eq!(restored.difficulty(), 65536.0);
+		assert_eq!(restored.seed.secret(None).unwrap(), secret);
+	}
+
+	#[test]
+	fn encrypted_checkpoint_roundtrip() {
+		let secret = Random.generate().unwrap().secret().clone();
+		let key_file = KeyFile::encrypt(&secret, "password", Kdf::pbkdf2(1024).unwrap()).unwrap();
+		let checkpoint = Checkpoint::new(vec![0xab], 1000, 1, SearchSeed::Encrypted(key_file));
+
+		let json = checkpoint.to_string();
+		assert!(!json.contains(&secret.to_string()));
+		let restored = Checkpoint::from_str(&json).unwrap();
+		assert_eq!(restored, checkpoint);
+		assert_eq!(restored.seed.secret(Some("password")).unwrap(), secret);
+		match restored.seed.secret(Some("wrong")) {
+			Err(Error::InvalidPassword) => (),
+			_ => panic!("expected invalid password"),
+		}
+		assert!(restored.seed.secret(None).is_err());
+	}
+
+	#[test]
+	fn invalid_checkpoint() {
+		assert!(Checkpoint::from_str("{}").is_err());
+		assert!(Checkpoint::from_str(r#"{"version":1,"prefix":"ab","iterations":10,"offsets":[]}"#).is_err());
+		assert!(Checkpoint::from_str(r#"{"version":1,"prefix":"ab","iterations":10,"offsets":[0]}"#).is_err());
+	}
+}
@@ -0,0 +1,64 @@
+use super::{Incremental, Matcher, Generator, Progress, KeyPair, Address, Error, contract_address};
+
+/// Tries to find keypair, which creates contract at address matching the pattern
+/// when deploying with given nonce.
+pub struct Deployer {
+	matcher: Matcher,
+	nonce: u64,
+	iterations: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl Deployer {
+	pub fn new(matcher: Matcher, nonce: u64, iterations: usize) -> Self {
+		Deployer::with_threads(matcher, nonce, iterations, 1)
+	}
+
+	pub fn with_threads(matcher: Matcher, nonce: u64, iterations: usize, threads: usize) -> Self {
+		Deployer::with_progress(matcher, nonce, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(matcher: Matcher, nonce: u64, iterations: usize, threads: usize, progress: Progress) -> Self {
+		Deployer {
+			matcher: matcher,
+			nonce: nonce,
+			iterations: iterations,
+			threads: threads,
+			progress: progress,
+		}
+	}
+}
+
+impl Generator for Deployer {
+	fn generate(self) -> Result<KeyPair, Error> {
+		let (matcher, nonce) = (self.matcher, self.nonce);
+		Incremental::with_progress(move |address: &Address| matcher.is_match(&contract_address(address, nonce)), self.iterations, self.threads, self.progress).generate()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use {Generator, Matcher, Deployer, contract_address};
+
+	#[test]
+	fn deployer_generator() {
+		let matcher = Matcher::from_str("ab").unwrap();
+		let keypair = Deployer::with_threads(matcher.clone(), 0, usize::max_value(), 2).generate().unwrap();
+		assert!(matcher.is_match(&contract_address(&keypair.address(), 0)));
+	}
+
+	#[test]
+	fn deployer_generator_nonce() {
+		let matcher = Matcher::from_str("...cd").unwrap();
+		let keypair = Deployer::new(matcher.clone(), 5, usize::max_value()).generate().unwrap();
+		assert!(matcher.is_match(&contract_address(&keypair.address(), 5)));
+	}
+
+	#[test]
+	fn deployer_generator_iterations_cap() {
+		let matcher = Matcher::from_str(&"0".repeat(40)).unwrap();
+		assert!(Deployer::new(matcher, 0, 1000).generate().is_err());
+	}
+}
@@ -0,0 +1,149 @@
+//! ECIES encryption compatible with devp2p and Parity's `ethcrypto::ecies`.
+//!
+//! Message format: `0x04 || ephemeral public (64) || iv (16) || ciphertext || mac (32)`,
+//! where keys are derived from ECDH shared secret with NIST SP 800-56 concatenation KDF,
+//! data is encrypted with AES-128-CTR and authenticated with HMAC-SHA256.
+
+use rand::Rng;
+use rand::os::OsRng;
+use secp256k1::{ecdh, key};
+use crypto::aes::{ctr, KeySize};
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::{Mac, MacResult};
+use crypto::sha2::Sha256;
+use super::{Generator, Random, Secret, Public, SECP256K1, Error};
+
+const OVERHEAD: usize = 1 + 64 + 16 + 32;
+
+/// ECDH agreement, returns x coordinate of `secret * public`.
+fn agree(secret: &Secret, public: &Public) -> Result<[u8; 32], Error> {
+	let context = &SECP256K1;
+	let pdata = {
+		let mut temp = [4u8; 65];
+		temp[1..65].copy_from_slice(&public[..]);
+		temp
+	};
+
+	let publ = try!(key::PublicKey::from_slice(context, &pdata));
+	let sec = try!(key::SecretKey::from_slice(context, &secret[..]));
+	let shared = ecdh::SharedSecret::new_raw(context, &publ, &sec);
+
+	let mut result = [0u8; 32];
+	result.copy_from_slice(&shared[0..32]);
+	Ok(result)
+}
+
+/// Derives encryption and mac keys from shared secret.
+fn derive_keys(shared: &[u8; 32]) -> ([u8; 16], [u8; 32]) {
+	// concatenation KDF with 32 bits counter, single round is enough for 32 bytes
+	let mut key = [0u8; 32];
+	let mut hasher = Sha256::new();
+	hasher.input(&[0, 0, 0, 1]);
+	hasher.input(shared);
+	hasher.result(&mut key);
+
+	let mut ekey = [0u8; 16];
+	ekey.copy_from_slice(&key[0..16]);
+
+	let mut mkey = [0u8; 32];
+	hasher.reset();
+	hasher.input(&key[16..32]);
+	hasher.result(&mut mkey);
+	(ekey, mkey)
+}
+
+fn mac(mkey: &[u8; 32], iv_and_ciphertext: &[u8], shared_mac: &[u8]) -> Hmac<Sha256> {
+	let mut hmac = Hmac::new(Sha256::new(), mkey);
+	hmac.input(iv_and_ciphertext);
+	hmac.input(shared_mac);
+	hmac
+}
+
+/// Encrypts `plain` so that only the owner of the `public` can decrypt it.
+/// `shared_mac` is additionally authenticated, but not included in the result.
+pub fn encrypt(public: &Public, shared_mac: &[u8], plain: &[u8]) -> Result<Vec<u8>, Error> {
+	let ephemeral = try!(Random.generate());
+	let (ekey, mkey) = derive_keys(&try!(agree(ephemeral.secret(), public)));
+
+	let mut iv = [0u8; 16];
+	try!(OsRng::new()).fill_bytes(&mut iv);
+
+	let mut msg = vec![0u8; OVERHEAD + plain.len()];
+	msg[0] = 0x04;
+	msg[1..65].copy_from_slice(&ephemeral.public()[..]);
+	msg[65..81].copy_from_slice(&iv);
+	ctr(KeySize::KeySize128, &ekey, &iv).process(plain, &mut msg[81..81 + plain.len()]);
+
+	let mac_start = 81 + plain.len();
+	let mut hmac = mac(&mkey, &msg[65..mac_start], shared_mac);
+	hmac.raw_result(&mut msg[mac_start..]);
+	Ok(msg)
+}
+
+/// Decrypts message encrypted for the public of the `secret`.
+pub fn decrypt(secret: &Secret, shared_mac: &[u8], encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+	if encrypted.len() < OVERHEAD || encrypted[0] != 0x04 {
+		return Err(Error::InvalidMessage);
+	}
+
+	let mut ephemeral = Public::default();
+	ephemeral.copy_from_slice(&encrypted[1..65]);
+	let (ekey, mkey) = derive_keys(&try!(agree(secret, &ephemeral)));
+
+	let mac_start = encrypted.len() - 32;
+	let mut hmac = mac(&mkey, &encrypted[65..mac_start], shared_mac);
+	// constant time comparison
+	if hmac.result() != MacResult::new(&encrypted[mac_start..]) {
+		return Err(Error::InvalidMessage);
+	}
+
+	let ciphertext = &encrypted[81..mac_start];
+	let mut plain = vec![0u8; ciphertext.len()];
+	ctr(KeySize::KeySize128, &ekey, &encrypted[65..81]).process(ciphertext, &mut plain);
+	Ok(plain)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use {Generator, Random, Secret};
+	use super::{encrypt, decrypt};
+
+	#[test]
+	fn encrypt_and_decrypt() {
+		let keypair = Random.generate().unwrap();
+		let message = b"So many Byzantine generals, so little time";
+		let encrypted = encrypt(keypair.public(), b"shared", message).unwrap();
+		assert_eq!(encrypted.len(), message.len() + 113);
+		assert_eq!(decrypt(keypair.secret(), b"shared", &encrypted).unwrap(), &message[..]);
+	}
+
+	#[test]
+	fn decrypt_fails_on_tampering() {
+		let keypair = Random.generate().unwrap();
+		let mut encrypted = encrypt(keypair.public(), &[], b"message").unwrap();
+		assert!(decrypt(keypair.secret(), b"other", &encrypted).is_err());
+
+		let other = Random.generate().unwrap();
+		assert!(decrypt(other.secret(), &[], &encrypted).is_err());
+
+		encrypted[90] ^= 1;
+		assert!(decrypt(keypair.secret(), &[], &encrypted).is_err());
+		assert!(decrypt(keypair.secret(), &[], &encrypted[..100]).is_err());
+	}
+
+	#[test]
+	fn decrypt_eip8_auth_vector() {
+		// pre-EIP-8 auth message from the devp2p handshake test vectors, sent to key B
+		let secret = Secret::from_str("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291").unwrap();
+		let auth = "048ca79ad18e4b0659fab4853fe5bc58eb83992980f4c9cc147d2aa31532efd29a3d3dc6a3d89eaf913150cfc777ce0ce4af2758bf4810235f6e6ceccfee1acc6b22c005e9e3a49d6448610a58e98744ba3ac0399e82692d67c1f58849050b3024e21a52c9d3b01d871ff5f210817912773e610443a9ef142e91cdba0bd77b5fdf0769b05671fc35f83d83e4d3b0b000c6b2a1b1bba89e0fc51bf4e460df3105c444f14be226458940d6061c296350937ffd5e3acaceeaaefd3c6f74be8e23e0f45163cc7ebd76220f0128410fd05250273156d548a414444ae2f7dea4dfca2d43c057adb701a715bf59f6fb66b2d1d20f2c703f851cbf5ac47396d9ca65b6260bd141ac4d53e2de585a73d1750780db4c9ee4cd4d225173a4592ee77e2bd94d0be3691f3b406f9bba9b591fc63facc016bfa8".from_hex().unwrap();
+
+		let plain = decrypt(&secret, &[], &auth).unwrap();
+		assert_eq!(plain.len(), 194);
+		// static public key of A followed by its nonce
+		assert_eq!(plain[97..161].to_hex(), "fda1cff674c90c9a197539fe3dfb53086ace64f83ed7c6eabec741f7f381cc803e52ab2cd55d5569bce4347107a310dfd5f88a010cd2ffd1005ca406f1842877");
+		assert_eq!(plain[161..193].to_hex(), "7e968bba13b6c50e2c4cd7f241cc0d64d1ac25c7f5952df231ac6a2bda8ee5d6");
+	}
+}
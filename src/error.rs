@@ -13,6 +13,28 @@ pub enum Error {
 	InvalidSignature,
 	/// Invalid AES message
 	InvalidMessage,
+	/// Invalid key derivation
+	InvalidDerivation,
+	/// Invalid mnemonic phrase
+	InvalidMnemonic,
+	/// Invalid or unsupported key file
+	InvalidKeyFile,
+	/// Key file password doesn't match
+	InvalidPassword,
+	/// Invalid EIP-712 typed data
+	InvalidTypedData(String),
+	/// Invalid vanity address pattern
+	InvalidPattern(String),
+	/// Invalid RLP encoding
+	InvalidRlp,
+	/// Invalid transaction
+	InvalidTransaction(String),
+	/// Invalid search checkpoint
+	InvalidCheckpoint,
+	/// Unknown address score
+	InvalidScore(String),
+	/// Search cancelled or out of time
+	Cancelled,
 	/// IO Error
 	Io(::std::io::Error),
 	/// Custom
@@ -27,6 +49,17 @@ impl fmt::Display for Error {
 			Error::InvalidAddress => "Invalid address".into(),
 			Error::InvalidSignature => "Invalid EC signature".into(),
 			Error::InvalidMessage => "Invalid AES message".into(),
+			Error::InvalidDerivation => "Invalid key derivation".into(),
+			Error::InvalidMnemonic => "Invalid mnemonic phrase".into(),
+			Error::InvalidKeyFile => "Invalid key file".into(),
+			Error::InvalidPassword => "Invalid password".into(),
+			Error::InvalidTypedData(ref s) => format!("Invalid typed data: {}", s),
+			Error::InvalidPattern(ref s) => format!("Invalid pattern: {}", s),
+			Error::InvalidRlp => "Invalid RLP".into(),
+			Error::InvalidTransaction(ref s) => format!("Invalid transaction: {}", s),
+			Error::InvalidCheckpoint => "Invalid checkpoint".into(),
+			Error::InvalidScore(ref s) => format!("Invalid score: {}", s),
+			Error::Cancelled => "Search cancelled".into(),
 			Error::Io(ref err) => format!("I/O error: {}", err),
 			Error::Custom(ref s) => s.clone(),
 		};
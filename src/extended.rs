@@ -0,0 +1,532 @@
+//! BIP32 hierarchical deterministic keys.
+
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::ripemd160::Ripemd160;
+use crypto::sha2::{Sha256, Sha512};
+use base58::{to_base58_check, from_base58_check};
+use math::{secret_add, public_add_secret, public_compress, public_decompress};
+use super::{Generator, KeyPair, Secret, Public, Error};
+
+const HARDENED_BIT: u32 = 0x8000_0000;
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// Single step of key derivation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Derivation {
+	/// Non-hardened derivation, possible from both extended secret and public.
+	Soft(u32),
+	/// Hardened derivation, possible only from extended secret.
+	Hard(u32),
+}
+
+impl Derivation {
+	/// Creates derivation from BIP32 child number.
+	pub fn from_index(index: u32) -> Self {
+		if index & HARDENED_BIT == 0 {
+			Derivation::Soft(index)
+		} else {
+			Derivation::Hard(index & !HARDENED_BIT)
+		}
+	}
+
+	/// BIP32 child number, with the highest bit set for hardened derivation.
+	pub fn index(&self) -> u32 {
+		match *self {
+			Derivation::Soft(index) => index,
+			Derivation::Hard(index) => index | HARDENED_BIT,
+		}
+	}
+}
+
+impl fmt::Display for Derivation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Derivation::Soft(index) => write!(f, "{}", index),
+			Derivation::Hard(index) => write!(f, "{}'", index),
+		}
+	}
+}
+
+impl FromStr for Derivation {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (number, hard) = match s.chars().last() {
+			Some('\'') | Some('h') | Some('H') => (&s[..s.len() - 1], true),
+			_ => (s, false),
+		};
+
+		match u32::from_str(number) {
+			Ok(index) if index & HARDENED_BIT == 0 => match hard {
+				true => Ok(Derivation::Hard(index)),
+				false => Ok(Derivation::Soft(index)),
+			},
+			_ => Err(Error::InvalidDerivation),
+		}
+	}
+}
+
+/// Derivation path, eg. `m/44'/60'/0'/0/7`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DerivationPath(Vec<Derivation>);
+
+impl DerivationPath {
+	pub fn new(derivations: Vec<Derivation>) -> Self {
+		DerivationPath(derivations)
+	}
+}
+
+impl Deref for DerivationPath {
+	type Target = [Derivation];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl fmt::Display for DerivationPath {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		try!(write!(f, "m"));
+		for derivation in &self.0 {
+			try!(write!(f, "/{}", derivation));
+		}
+		Ok(())
+	}
+}
+
+impl FromStr for DerivationPath {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.split('/');
+		match parts.next() {
+			Some("m") | Some("M") => (),
+			_ => return Err(Error::InvalidDerivation),
+		}
+
+		let mut derivations = Vec::new();
+		for part in parts {
+			derivations.push(try!(Derivation::from_str(part)));
+		}
+		Ok(DerivationPath(derivations))
+	}
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+	let mut hmac = Hmac::new(Sha512::new(), key);
+	hmac.input(data);
+	let mut result = [0u8; 64];
+	hmac.raw_result(&mut result);
+
+	let mut left = [0u8; 32];
+	let mut right = [0u8; 32];
+	left.copy_from_slice(&result[0..32]);
+	right.copy_from_slice(&result[32..64]);
+	(left, right)
+}
+
+fn fingerprint(public: &Public) -> Result<[u8; 4], Error> {
+	let compressed = try!(public_compress(public));
+	let mut sha = [0u8; 32];
+	let mut hasher = Sha256::new();
+	hasher.input(&compressed);
+	hasher.result(&mut sha);
+
+	let mut hash = [0u8; 20];
+	let mut hasher = Ripemd160::new();
+	hasher.input(&sha);
+	hasher.result(&mut hash);
+
+	let mut result = [0u8; 4];
+	result.copy_from_slice(&hash[0..4]);
+	Ok(result)
+}
+
+fn to_u32(data: &[u8]) -> u32 {
+	(data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+}
+
+fn from_u32(value: u32) -> [u8; 4] {
+	[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+/// Key metadata shared by extended secret and public.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ExtendedInfo {
+	chain_code: [u8; 32],
+	depth: u8,
+	parent_fingerprint: [u8; 4],
+	index: u32,
+}
+
+impl ExtendedInfo {
+	fn serialize(&self, version: &[u8; 4], key: &[u8]) -> String {
+		let mut data = Vec::with_capacity(78);
+		data.extend_from_slice(version);
+		data.push(self.depth);
+		data.extend_from_slice(&self.parent_fingerprint);
+		data.extend_from_slice(&from_u32(self.index));
+		data.extend_from_slice(&self.chain_code);
+		data.extend_from_slice(key);
+		to_base58_check(&data)
+	}
+
+	fn deserialize(s: &str, version: &[u8; 4]) -> Option<(ExtendedInfo, Vec<u8>)> {
+		let data = match from_base58_check(s) {
+			Some(ref data) if data.len() == 78 && &data[0..4] == version => data.clone(),
+			_ => return None,
+		};
+
+		let mut info = ExtendedInfo {
+			chain_code: [0u8; 32],
+			depth: data[4],
+			parent_fingerprint: [0u8; 4],
+			index: to_u32(&data[9..13]),
+		};
+		info.parent_fingerprint.copy_from_slice(&data[5..9]);
+		info.chain_code.copy_from_slice(&data[13..45]);
+
+		if info.depth == 0 && (info.parent_fingerprint != [0u8; 4] || info.index != 0) {
+			return None;
+		}
+
+		Some((info, data[45..78].to_vec()))
+	}
+
+	fn child(&self, parent_fingerprint: [u8; 4], derivation: Derivation, chain_code: [u8; 32]) -> Result<ExtendedInfo, Error> {
+		if self.depth == u8::max_value() {
+			return Err(Error::InvalidDerivation);
+		}
+
+		Ok(ExtendedInfo {
+			chain_code: chain_code,
+			depth: self.depth + 1,
+			parent_fingerprint: parent_fingerprint,
+			index: derivation.index(),
+		})
+	}
+}
+
+/// Extended secret key, a secret with chain code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedSecret {
+	secret: Secret,
+	info: ExtendedInfo,
+}
+
+impl ExtendedSecret {
+	/// Creates master key from existing secret and chain code.
+	pub fn new(secret: Secret, chain_code: [u8; 32]) -> Self {
+		ExtendedSecret {
+			secret: secret,
+			info: ExtendedInfo {
+				chain_code: chain_code,
+				depth: 0,
+				parent_fingerprint: [0u8; 4],
+				index: 0,
+			},
+		}
+	}
+
+	/// Creates master key from seed as defined in BIP32.
+	pub fn from_seed(seed: &[u8]) -> Result<Self, Error> {
+		let (secret, chain_code) = hmac_sha512(b"Bitcoin seed", seed);
+		let secret = Secret::from(secret);
+		// make sure that secret is valid
+		try!(KeyPair::from_secret(secret.clone()));
+		Ok(ExtendedSecret::new(secret, chain_code))
+	}
+
+	pub fn secret(&self) -> &Secret {
+		&self.secret
+	}
+
+	pub fn chain_code(&self) -> &[u8; 32] {
+		&self.info.chain_code
+	}
+
+	/// Depth of this key in the derivation tree. Master key has depth 0.
+	pub fn depth(&self) -> u8 {
+		self.info.depth
+	}
+
+	/// Returns matching extended public.
+	pub fn public(&self) -> Result<ExtendedPublic, Error> {
+		let keypair = try!(KeyPair::from_secret(self.secret.clone()));
+		Ok(ExtendedPublic {
+			public: keypair.public().clone(),
+			info: self.info.clone(),
+		})
+	}
+
+	/// Derives child extended secret.
+	pub fn derive(&self, derivation: Derivation) -> Result<ExtendedSecret, Error> {
+		let keypair = try!(KeyPair::from_secret(self.secret.clone()));
+		let mut data = Vec::with_capacity(37);
+		match derivation {
+			Derivation::Soft(_) => data.extend_from_slice(&try!(public_compress(keypair.public()))),
+			Derivation::Hard(_) => {
+				data.push(0);
+				data.extend_from_slice(&self.secret[..]);
+			},
+		}
+		data.extend_from_slice(&from_u32(derivation.index()));
+
+		let (tweak, chain_code) = hmac_sha512(&self.info.chain_code, &data);
+		let mut secret = self.secret.clone();
+		try!(secret_add(&mut secret, &Secret::from(tweak)));
+
+		Ok(ExtendedSecret {
+			secret: secret,
+			info: try!(self.info.child(try!(fingerprint(keypair.public())), derivation, chain_code)),
+		})
+	}
+
+	/// Derives extended secret for every step of the path.
+	pub fn derive_path(&self, path: &DerivationPath) -> Result<ExtendedSecret, Error> {
+		path.iter().fold(Ok(self.clone()), |key, derivation| key.and_then(|key| key.derive(*derivation)))
+	}
+}
+
+impl Generator for ExtendedSecret {
+	fn generate(self) -> Result<KeyPair, Error> {
+		KeyPair::from_secret(self.secret)
+	}
+}
+
+impl fmt::Display for ExtendedSecret {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		let mut key = [0u8; 33];
+		key[1..33].copy_from_slice(&self.secret[..]);
+		write!(f, "{}", self.info.serialize(&XPRV_VERSION, &key))
+	}
+}
+
+impl FromStr for ExtendedSecret {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (info, key) = match ExtendedInfo::deserialize(s, &XPRV_VERSION) {
+			Some((ref info, ref key)) if key[0] == 0 => (info.clone(), key.clone()),
+			_ => return Err(Error::InvalidSecret),
+		};
+
+		let mut secret = Secret::default();
+		secret.copy_from_slice(&key[1..33]);
+		try!(KeyPair::from_secret(secret.clone()));
+
+		Ok(ExtendedSecret {
+			secret: secret,
+			info: info,
+		})
+	}
+}
+
+/// Extended public key, a public with chain code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublic {
+	public: Public,
+	info: ExtendedInfo,
+}
+
+impl ExtendedPublic {
+	/// Creates master key from existing public and chain code.
+	pub fn new(public: Public, chain_code: [u8; 32]) -> Self {
+		ExtendedPublic {
+			public: public,
+			info: ExtendedInfo {
+				chain_code: chain_code,
+				depth: 0,
+				parent_fingerprint: [0u8; 4],
+				index: 0,
+			},
+		}
+	}
+
+	pub fn public(&self) -> &Public {
+		&self.public
+	}
+
+	pub fn chain_code(&self) -> &[u8; 32] {
+		&self.info.chain_code
+	}
+
+	/// Depth of this key in the derivation tree. Master key has depth 0.
+	pub fn depth(&self) -> u8 {
+		self.info.depth
+	}
+
+	/// Derives child extended public. Fails for hardened derivation.
+	pub fn derive(&self, derivation: Derivation) -> Result<ExtendedPublic, Error> {
+		if let Derivation::Hard(_) = derivation {
+			return Err(Error::InvalidDerivation);
+		}
+
+		let mut data = Vec::with_capacity(37);
+		data.extend_from_slice(&try!(public_compress(&self.public)));
+		data.extend_from_slice(&from_u32(derivation.index()));
+
+		let (tweak, chain_code) = hmac_sha512(&self.info.chain_code, &data);
+		let mut public = self.public.clone();
+		try!(public_add_secret(&mut public, &Secret::from(tweak)));
+
+		Ok(ExtendedPublic {
+			public: public,
+			info: try!(self.info.child(try!(fingerprint(&self.public)), derivation, chain_code)),
+		})
+	}
+
+	/// Derives extended public for every step of the path.
+	pub fn derive_path(&self, path: &DerivationPath) -> Result<ExtendedPublic, Error> {
+		path.iter().fold(Ok(self.clone()), |key, derivation| key.and_then(|key| key.derive(*derivation)))
+	}
+}
+
+impl fmt::Display for ExtendedPublic {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match public_compress(&self.public) {
+			Ok(key) => write!(f, "{}", self.info.serialize(&XPUB_VERSION, &key)),
+			Err(_) => Err(fmt::Error),
+		}
+	}
+}
+
+impl FromStr for ExtendedPublic {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match ExtendedInfo::deserialize(s, &XPUB_VERSION) {
+			Some((info, key)) => Ok(ExtendedPublic {
+				public: try!(public_decompress(&key)),
+				info: info,
+			}),
+			None => Err(Error::InvalidPublic),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::FromHex;
+	use {Generator, KeyPair};
+	use super::{ExtendedSecret, ExtendedPublic, Derivation, DerivationPath};
+
+	fn check_vector(master: &ExtendedSecret, path: &str, xpub: &str, xprv: &str) {
+		let path = DerivationPath::from_str(path).unwrap();
+		let derived = master.derive_path(&path).unwrap();
+		assert_eq!(derived.to_string(), xprv);
+		assert_eq!(derived.public().unwrap().to_string(), xpub);
+		assert_eq!(ExtendedSecret::from_str(xprv).unwrap(), derived);
+		assert_eq!(ExtendedPublic::from_str(xpub).unwrap(), derived.public().unwrap());
+	}
+
+	#[test]
+	fn bip32_test_vector_1() {
+		let seed = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+		let master = ExtendedSecret::from_seed(&seed).unwrap();
+
+		check_vector(&master, "m",
+			"xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+			"xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi");
+		check_vector(&master, "m/0'",
+			"xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+			"xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7");
+		check_vector(&master, "m/0'/1",
+			"xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+			"xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs");
+		check_vector(&master, "m/0'/1/2'",
+			"xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+			"xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM");
+		check_vector(&master, "m/0'/1/2'/2",
+			"xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+			"xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334");
+		check_vector(&master, "m/0'/1/2'/2/1000000000",
+			"xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+			"xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76");
+	}
+
+	#[test]
+	fn bip32_test_vector_2() {
+		let seed = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542".from_hex().unwrap();
+		let master = ExtendedSecret::from_seed(&seed).unwrap();
+
+		check_vector(&master, "m",
+			"xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+			"xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U");
+		check_vector(&master, "m/0",
+			"xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+			"xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt");
+		check_vector(&master, "m/0/2147483647'",
+			"xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a",
+			"xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9");
+		check_vector(&master, "m/0/2147483647'/1",
+			"xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon",
+			"xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef");
+		check_vector(&master, "m/0/2147483647'/1/2147483646'",
+			"xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL",
+			"xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc");
+		check_vector(&master, "m/0/2147483647'/1/2147483646'/2",
+			"xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt",
+			"xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j");
+
+		// non-hardened children derived from public parents only
+		let master_public = master.public().unwrap();
+		assert_eq!(master_public.derive(Derivation::Soft(0)).unwrap().to_string(),
+			"xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH");
+		let parent = ExtendedPublic::from_str("xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL").unwrap();
+		assert_eq!(parent.derive(Derivation::Soft(2)).unwrap().to_string(),
+			"xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt");
+	}
+
+	#[test]
+	fn bip32_test_vector_3() {
+		// master secret and its hardened child keep leading zeros
+		let seed = "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be".from_hex().unwrap();
+		let master = ExtendedSecret::from_seed(&seed).unwrap();
+
+		check_vector(&master, "m",
+			"xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13",
+			"xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6");
+		check_vector(&master, "m/0'",
+			"xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+			"xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L");
+	}
+
+	#[test]
+	fn public_derivation_matches_secret_derivation() {
+		let seed = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+		let account = ExtendedSecret::from_seed(&seed).unwrap()
+			.derive_path(&DerivationPath::from_str("m/44'/60'/0'").unwrap()).unwrap();
+		let path = DerivationPath::from_str("m/0/7").unwrap();
+
+		let secret = account.derive_path(&path).unwrap();
+		let public = account.public().unwrap().derive_path(&path).unwrap();
+		assert_eq!(secret.public().unwrap(), public);
+		assert!(account.public().unwrap().derive(Derivation::Hard(0)).is_err());
+	}
+
+	#[test]
+	fn derived_secret_is_generator() {
+		let seed = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+		let child = ExtendedSecret::from_seed(&seed).unwrap().derive(Derivation::Hard(0)).unwrap();
+		let expected = KeyPair::from_secret(child.secret().clone()).unwrap();
+		assert_eq!(child.generate().unwrap().public(), expected.public());
+	}
+
+	#[test]
+	fn derivation_path_from_str() {
+		let path = DerivationPath::from_str("m/44'/60'/0'/0/7").unwrap();
+		assert_eq!(&path[..], &[Derivation::Hard(44), Derivation::Hard(60), Derivation::Hard(0), Derivation::Soft(0), Derivation::Soft(7)]);
+		assert_eq!(path.to_string(), "m/44'/60'/0'/0/7");
+		assert!(DerivationPath::from_str("44'/60'").is_err());
+		assert!(DerivationPath::from_str("m/2147483648").is_err());
+		assert!(DerivationPath::from_str("m/a").is_err());
+	}
+}
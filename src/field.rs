@@ -0,0 +1,243 @@
+//! Arithmetic modulo secp256k1 field prime `p = 2^256 - 2^32 - 977`.
+
+use std::ops::{Add, Sub, Mul, Neg};
+
+/// `2^256 - p`, reduces carries above 256 bits.
+const C: u64 = 0x1_0000_03d1;
+
+const P: [u64; 4] = [0xffff_fffe_ffff_fc2f, 0xffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff];
+
+/// Field element, four 64 bits limbs, least significant first. Always fully reduced.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FieldElement([u64; 4]);
+
+fn ge_p(limbs: &[u64; 4]) -> bool {
+	for i in (0..4).rev() {
+		if limbs[i] != P[i] {
+			return limbs[i] > P[i];
+		}
+	}
+	true
+}
+
+/// Adds `carry * 2^256` to limbs, folding it back as `carry * C`.
+fn fold(mut limbs: [u64; 4], mut carry: u128) -> FieldElement {
+	while carry != 0 {
+		let mut c = carry * C as u128;
+		for limb in limbs.iter_mut() {
+			let v = *limb as u128 + c;
+			*limb = v as u64;
+			c = v >> 64;
+		}
+		carry = c;
+	}
+
+	if ge_p(&limbs) {
+		let mut borrow = 0u128;
+		for i in 0..4 {
+			let v = (limbs[i] as u128).wrapping_sub(P[i] as u128 + borrow);
+			limbs[i] = v as u64;
+			borrow = (v >> 127) & 1;
+		}
+	}
+	FieldElement(limbs)
+}
+
+impl FieldElement {
+	/// Returns element from 32 bytes big endian value, `None` if it's not below `p`.
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+		assert_eq!(bytes.len(), 32);
+		let mut limbs = [0u64; 4];
+		for (i, chunk) in bytes.chunks(8).enumerate() {
+			limbs[3 - i] = chunk.iter().fold(0u64, |limb, b| (limb << 8) | *b as u64);
+		}
+
+		match ge_p(&limbs) {
+			true => None,
+			false => Some(FieldElement(limbs)),
+		}
+	}
+
+	/// Writes 32 bytes big endian value.
+	pub fn write_bytes(&self, bytes: &mut [u8]) {
+		for (i, chunk) in bytes.chunks_mut(8).enumerate() {
+			let limb = self.0[3 - i];
+			for (j, b) in chunk.iter_mut().enumerate() {
+				*b = (limb >> (56 - 8 * j)) as u8;
+			}
+		}
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.0 == [0u64; 4]
+	}
+
+	pub fn square(&self) -> Self {
+		*self * *self
+	}
+
+	/// Returns multiplicative inverse, `a^(p - 2)`. Inverse of zero is zero.
+	pub fn invert(&self) -> Self {
+		let mut exponent = P;
+		exponent[0] -= 2;
+
+		let mut result = FieldElement([1, 0, 0, 0]);
+		for i in (0..256).rev() {
+			result = result.square();
+			if (exponent[i / 64] >> (i % 64)) & 1 == 1 {
+				result = result * *self;
+			}
+		}
+		result
+	}
+}
+
+impl Add for FieldElement {
+	type Output = FieldElement;
+
+	fn add(self, other: Self) -> Self {
+		let mut limbs = [0u64; 4];
+		let mut carry = 0u128;
+		for i in 0..4 {
+			let v = self.0[i] as u128 + other.0[i] as u128 + carry;
+			limbs[i] = v as u64;
+			carry = v >> 64;
+		}
+		fold(limbs, carry)
+	}
+}
+
+impl Neg for FieldElement {
+	type Output = FieldElement;
+
+	fn neg(self) -> Self {
+		if self.is_zero() {
+			return self;
+		}
+
+		let mut limbs = [0u64; 4];
+		let mut borrow = 0u128;
+		for i in 0..4 {
+			let v = (P[i] as u128).wrapping_sub(self.0[i] as u128 + borrow);
+			limbs[i] = v as u64;
+			borrow = (v >> 127) & 1;
+		}
+		FieldElement(limbs)
+	}
+}
+
+impl Sub for FieldElement {
+	type Output = FieldElement;
+
+	fn sub(self, other: Self) -> Self {
+		self + -other
+	}
+}
+
+impl Mul for FieldElement {
+	type Output = FieldElement;
+
+	fn mul(self, other: Self) -> Self {
+		let mut product = [0u64; 8];
+		for i in 0..4 {
+			let mut carry = 0u128;
+			for j in 0..4 {
+				let v = product[i + j] as u128 + self.0[i] as u128 * other.0[j] as u128 + carry;
+				product[i + j] = v as u64;
+				carry = v >> 64;
+			}
+			product[i + 4] = carry as u64;
+		}
+
+		// 2^256 = C (mod p)
+		let mut limbs = [0u64; 4];
+		let mut carry = 0u128;
+		for i in 0..4 {
+			let v = product[i] as u128 + product[i + 4] as u128 * C as u128 + carry;
+			limbs[i] = v as u64;
+			carry = v >> 64;
+		}
+		fold(limbs, carry)
+	}
+}
+
+/// Replaces each element with its inverse, using single field inversion.
+/// Returns false and leaves elements unspecified if any of them is zero.
+pub fn batch_invert(elements: &mut [FieldElement], scratch: &mut Vec<FieldElement>) -> bool {
+	if elements.is_empty() {
+		return true;
+	}
+
+	scratch.clear();
+	let mut accumulator = FieldElement([1, 0, 0, 0]);
+	for element in elements.iter() {
+		accumulator = accumulator * *element;
+		scratch.push(accumulator);
+	}
+
+	if accumulator.is_zero() {
+		return false;
+	}
+
+	let mut inverse = accumulator.invert();
+	for i in (1..elements.len()).rev() {
+		let element_inverse = inverse * scratch[i - 1];
+		inverse = inverse * elements[i];
+		elements[i] = element_inverse;
+	}
+	elements[0] = inverse;
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use super::{FieldElement, batch_invert};
+
+	fn element(hex: &str) -> FieldElement {
+		FieldElement::from_bytes(&hex.from_hex().unwrap()).unwrap()
+	}
+
+	fn to_hex(element: &FieldElement) -> String {
+		let mut bytes = [0u8; 32];
+		element.write_bytes(&mut bytes);
+		bytes.to_hex()
+	}
+
+	#[test]
+	fn generator_on_curve() {
+		let x = element("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+		let y = element("483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8");
+		let seven = element("0000000000000000000000000000000000000000000000000000000000000007");
+		assert_eq!(y.square(), x.square() * x + seven);
+	}
+
+	#[test]
+	fn reduction() {
+		let minus_one = element("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e");
+		let one = element("0000000000000000000000000000000000000000000000000000000000000001");
+		assert!((minus_one + one).is_zero());
+		assert_eq!(minus_one * minus_one, one);
+		assert_eq!(to_hex(&(one - minus_one)), "0000000000000000000000000000000000000000000000000000000000000002");
+		assert!(FieldElement::from_bytes(&"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f".from_hex().unwrap()).is_none());
+	}
+
+	#[test]
+	fn inversion() {
+		let one = element("0000000000000000000000000000000000000000000000000000000000000001");
+		let mut elements = vec![
+			element("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+			element("483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"),
+			element("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e"),
+		];
+		let original = elements.clone();
+		assert!(batch_invert(&mut elements, &mut Vec::new()));
+		for (element, inverse) in original.iter().zip(elements.iter()) {
+			assert_eq!(*element * *inverse, one);
+			assert_eq!(element.invert(), *inverse);
+		}
+
+		elements.push(FieldElement::default());
+		assert!(!batch_invert(&mut elements, &mut Vec::new()));
+	}
+}
@@ -0,0 +1,460 @@
+//! Keypair search walking consecutive secrets `k + 1, k + 2, ...` from single random secret `k`.
+//!
+//! Public keys are computed by affine point addition of precomputed multiples of the generator,
+//! normalized in batches with single field inversion. `KeyPair` is created only for the match.
+//!
+//! Resumable search is deterministic, worker `i` walks from `seed + i * 2^64`, so that its state
+//! is just the seed and number of secrets tried by each worker.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use std::{cmp, thread};
+use keccak::Keccak256;
+use field::{FieldElement, batch_invert};
+use math::{secret_add, public_add_secret};
+use super::{Random, Generator, Progress, KeyPair, Secret, Public, Address, Error};
+
+/// Number of public keys computed with single field inversion.
+const BATCH: usize = 256;
+
+lazy_static! {
+	/// Affine multiples `G, 2G, ..., BATCH * G` of the generator.
+	static ref MULTIPLES: Vec<(FieldElement, FieldElement)> = (1..BATCH as u64 + 1).map(|i| {
+		let keypair = KeyPair::from_secret(scalar(i)).expect("small scalars are valid secrets; qed");
+		point(keypair.public())
+	}).collect();
+}
+
+fn scalar(value: u64) -> Secret {
+	let mut secret = Secret::default();
+	secret[24..32].copy_from_slice(&value.to_be_bytes());
+	secret
+}
+
+/// Returns `lane * 2^64 + offset`.
+fn lane_scalar(lane: u64, offset: u64) -> Secret {
+	let mut secret = scalar(offset);
+	secret[16..24].copy_from_slice(&lane.to_be_bytes());
+	secret
+}
+
+fn point(public: &Public) -> (FieldElement, FieldElement) {
+	let x = FieldElement::from_bytes(&public[0..32]).expect("public coordinates are field elements; qed");
+	let y = FieldElement::from_bytes(&public[32..64]).expect("public coordinates are field elements; qed");
+	(x, y)
+}
+
+/// Walk over public keys of consecutive secrets.
+pub struct Walk {
+	secret: Secret,
+	x: FieldElement,
+	y: FieldElement,
+	offset: u64,
+	inverses: Vec<FieldElement>,
+	scratch: Vec<FieldElement>,
+}
+
+impl Walk {
+	/// Starts walk at the keypair.
+	pub fn new(keypair: &KeyPair) -> Self {
+		Walk::from_parts(keypair.secret().clone(), keypair.public())
+	}
+
+	/// Starts walk at `base + keypair`. Secrets of the walk are offsets from the secret of `base`.
+	pub fn with_base(base: &Public, keypair: &KeyPair) -> Result<Self, Error> {
+		let mut public = base.clone();
+		try!(public_add_secret(&mut public, keypair.secret()));
+		Ok(Walk::from_parts(keypair.secret().clone(), &public))
+	}
+
+	fn from_parts(secret: Secret, public: &Public) -> Self {
+		let (x, y) = point(public);
+		Walk {
+			secret: secret,
+			x: x,
+			y: y,
+			offset: 0,
+			inverses: Vec::with_capacity(BATCH),
+			scratch: Vec::with_capacity(BATCH),
+		}
+	}
+
+	/// Computes publics of the next `count` secrets, at most `BATCH`.
+	/// `publics[i]` belongs to `self.secret(offset + i + 1)`, where offset is the value before the call.
+	/// Fails if the walk hits the point at infinity, which is practically impossible for random start.
+	pub fn next_batch(&mut self, count: usize, publics: &mut Vec<Public>) -> Result<(), Error> {
+		let count = cmp::min(count, BATCH);
+		self.inverses.clear();
+		for &(ref x, _) in &MULTIPLES[..count] {
+			self.inverses.push(*x - self.x);
+		}
+
+		if !batch_invert(&mut self.inverses, &mut self.scratch) {
+			return Err(Error::Custom("Search reached point at infinity".into()));
+		}
+
+		publics.clear();
+		for (&(ref x, ref y), inverse) in MULTIPLES[..count].iter().zip(self.inverses.iter()) {
+			let lambda = (*y - self.y) * *inverse;
+			let x3 = lambda.square() - self.x - *x;
+			let y3 = lambda * (self.x - x3) - self.y;
+
+			let mut public = Public::default();
+			x3.write_bytes(&mut public[0..32]);
+			y3.write_bytes(&mut public[32..64]);
+			publics.push(public);
+		}
+
+		let last = point(&publics[count - 1]);
+		self.x = last.0;
+		self.y = last.1;
+		self.offset += count as u64;
+		Ok(())
+	}
+
+	/// Current offset from the starting secret.
+	pub fn offset(&self) -> u64 {
+		self.offset
+	}
+
+	/// Returns secret of the walk at given offset.
+	pub fn secret(&self, offset: u64) -> Result<Secret, Error> {
+		let mut secret = self.secret.clone();
+		if offset != 0 {
+			try!(secret_add(&mut secret, &scalar(offset)));
+		}
+		Ok(secret)
+	}
+}
+
+/// Searches for keypair with address matching the predicate, on multiple threads.
+pub struct Incremental<F> {
+	predicate: F,
+	iterations: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl<F> Incremental<F> where F: Fn(&Address) -> bool + Send + Sync + 'static {
+	pub fn new(predicate: F, iterations: usize) -> Self {
+		Incremental::with_threads(predicate, iterations, 1)
+	}
+
+	pub fn with_threads(predicate: F, iterations: usize, threads: usize) -> Self {
+		Incremental::with_progress(predicate, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(predicate: F, iterations: usize, threads: usize, progress: Progress) -> Self {
+		Incremental {
+			predicate: predicate,
+			iterations: iterations,
+			threads: cmp::max(threads, 1),
+			progress: progress,
+		}
+	}
+}
+
+/// Walks from random secrets until predicate matches, `done` is set, `progress` is cancelled or `attempts` reach `iterations`.
+/// Returns secret of the matching walk point.
+fn search<F>(base: Option<&Public>, predicate: &F, iterations: usize, done: &AtomicBool, attempts: &AtomicUsize, progress: &Progress) -> Option<Result<Secret, Error>> where F: Fn(&Address) -> bool {
+	let mut publics = Vec::with_capacity(BATCH);
+	'restart: loop {
+		let start = Random.generate().and_then(|keypair| match base {
+			Some(base) => Walk::with_base(base, &keypair),
+			None => Ok(Walk::new(&keypair)),
+		});
+		let mut walk = match start {
+			Ok(walk) => walk,
+			Err(err) => return Some(Err(err)),
+		};
+
+		while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+			let claimed = attempts.fetch_add(BATCH, Ordering::Relaxed);
+			if claimed >= iterations {
+				return None;
+			}
+
+			let start = walk.offset();
+			if walk.next_batch(iterations - claimed, &mut publics).is_err() {
+				continue 'restart;
+			}
+			progress.counter().fetch_add(publics.len(), Ordering::Relaxed);
+
+			for (i, public) in publics.iter().enumerate() {
+				let hash = public.keccak256();
+				let mut address = Address::default();
+				address.copy_from_slice(&hash[12..]);
+				if predicate(&address) {
+					return Some(walk.secret(start + i as u64 + 1));
+				}
+			}
+		}
+
+		return None;
+	}
+}
+
+/// Fails with the reason the search stopped without match.
+fn not_found(progress: &Progress) -> Error {
+	match progress.is_cancelled() {
+		true => Error::Cancelled,
+		false => Error::Custom("Could not find keypair".into()),
+	}
+}
+
+/// Runs `worker` on `threads` threads and returns the first result any of them finds. Workers stop once the flag
+/// they are given is set and return `None` when they give up. Returns `None` if all of them give up.
+/// Searchers built on it follow the contract documented on `Progress`.
+pub fn run_workers<T, F>(threads: usize, progress: &Progress, worker: F) -> Result<Option<T>, Error>
+	where T: Send + 'static, F: Fn(&AtomicBool) -> Option<Result<T, Error>> + Send + Sync + 'static {
+	let worker = Arc::new(worker);
+	let done = Arc::new(AtomicBool::new(false));
+	let (tx, rx) = mpsc::channel();
+
+	let workers = (0..cmp::max(threads, 1)).map(|_| {
+		let (worker, done, tx) = (worker.clone(), done.clone(), tx.clone());
+		thread::spawn(move || {
+			if let Some(result) = worker(&done) {
+				done.store(true, Ordering::Relaxed);
+				let _ = tx.send(result);
+			}
+		})
+	}).collect::<Vec<_>>();
+
+	// workers hold the only remaining senders, so `recv` fails once all of them give up
+	drop(tx);
+	let result = rx.recv();
+	done.store(true, Ordering::Relaxed);
+	for worker in workers {
+		let _ = worker.join();
+	}
+
+	match result {
+		Ok(result) => result.map(Some),
+		Err(_) if progress.is_cancelled() => Err(Error::Cancelled),
+		Err(_) => Ok(None),
+	}
+}
+
+/// Runs search on `threads` workers, returns secret of the first match.
+pub fn run<F>(base: Option<Public>, predicate: F, iterations: usize, threads: usize, progress: &Progress) -> Result<Secret, Error> where F: Fn(&Address) -> bool + Send + Sync + 'static {
+	let attempts = AtomicUsize::new(0);
+	let worker_progress = progress.clone();
+	let found = try!(run_workers(threads, progress, move |done| search(base.as_ref(), &predicate, iterations, done, &attempts, &worker_progress)));
+	found.ok_or_else(|| not_found(progress))
+}
+
+/// Walks from `start` until predicate matches, `done` is set, `progress` is cancelled or `attempts` reach `iterations`.
+/// Stores number of secrets tried after `start` in `tried`.
+fn search_from<F>(start: Secret, predicate: &F, iterations: usize, done: &AtomicBool, attempts: &AtomicUsize, tried: &AtomicUsize, progress: &Progress) -> Option<Result<Secret, Error>> where F: Fn(&Address) -> bool {
+	let mut walk = match KeyPair::from_secret(start) {
+		Ok(keypair) => Walk::new(&keypair),
+		Err(err) => return Some(Err(err)),
+	};
+	let mut publics = Vec::with_capacity(BATCH);
+
+	while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+		let claimed = attempts.fetch_add(BATCH, Ordering::Relaxed);
+		if claimed >= iterations {
+			return None;
+		}
+
+		let start = walk.offset();
+		// deterministic walk can't restart elsewhere
+		if let Err(err) = walk.next_batch(iterations - claimed, &mut publics) {
+			return Some(Err(err));
+		}
+		progress.counter().fetch_add(publics.len(), Ordering::Relaxed);
+
+		for (i, public) in publics.iter().enumerate() {
+			let hash = public.keccak256();
+			let mut address = Address::default();
+			address.copy_from_slice(&hash[12..]);
+			if predicate(&address) {
+				return Some(walk.secret(start + i as u64 + 1));
+			}
+		}
+		tried.store(walk.offset() as usize, Ordering::Relaxed);
+	}
+
+	None
+}
+
+/// Runs resumable search with worker for each of `offsets`, worker `i` tries secrets after `seed + i * 2^64 + offsets[i]`.
+/// `iterations` includes secrets tried before. Calls `checkpoint` with current offsets every `interval` and once more
+/// when the search stops. Secrets up to the offsets were already tried.
+pub fn run_resumable<F, C>(seed: &Secret, offsets: &[u64], predicate: F, iterations: usize, interval: Duration, progress: &Progress, mut checkpoint: C) -> Result<Secret, Error>
+	where F: Fn(&Address) -> bool + Send + Sync + 'static, C: FnMut(&[u64]) -> Result<(), Error> {
+	let predicate = Arc::new(predicate);
+	let done = Arc::new(AtomicBool::new(false));
+	let attempts = Arc::new(AtomicUsize::new(offsets.iter().fold(0usize, |sum, offset| sum.saturating_add(*offset as usize))));
+	let tried = Arc::new((0..offsets.len()).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>());
+	let (tx, rx) = mpsc::channel();
+
+	let mut workers = Vec::with_capacity(offsets.len());
+	for (lane, offset) in offsets.iter().enumerate() {
+		let mut start = seed.clone();
+		if lane != 0 || *offset != 0 {
+			try!(secret_add(&mut start, &lane_scalar(lane as u64, *offset)));
+		}
+		let (predicate, done, attempts, tried, progress, tx) = (predicate.clone(), done.clone(), attempts.clone(), tried.clone(), progress.clone(), tx.clone());
+		workers.push(thread::spawn(move || {
+			if let Some(result) = search_from(start, &*predicate, iterations, &done, &attempts, &tried[lane], &progress) {
+				done.store(true, Ordering::Relaxed);
+				let _ = tx.send(result);
+			}
+		}));
+	}
+
+	let current = |tried: &[AtomicUsize]| offsets.iter().zip(tried.iter()).map(|(offset, tried)| offset + tried.load(Ordering::Relaxed) as u64).collect::<Vec<_>>();
+
+	drop(tx);
+	let result = loop {
+		match rx.recv_timeout(interval) {
+			Ok(result) => break Some(result),
+			Err(RecvTimeoutError::Timeout) => if let Err(err) = checkpoint(&current(&tried)) {
+				break Some(Err(err));
+			},
+			Err(RecvTimeoutError::Disconnected) => break None,
+		}
+	};
+	done.store(true, Ordering::Relaxed);
+	for worker in workers {
+		let _ = worker.join();
+	}
+	try!(checkpoint(&current(&tried)));
+
+	match result {
+		Some(result) => result,
+		None => Err(not_found(progress)),
+	}
+}
+
+impl<F> Generator for Incremental<F> where F: Fn(&Address) -> bool + Send + Sync + 'static {
+	fn generate(self) -> Result<KeyPair, Error> {
+		let secret = try!(run(None, self.predicate, self.iterations, self.threads, &self.progress));
+		KeyPair::from_secret(secret)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+	use std::sync::atomic::Ordering;
+	use std::time::Duration;
+	use {Generator, Random, Progress, KeyPair, Address, Error};
+	use math::secret_add;
+	use super::{Walk, Incremental, BATCH, run_resumable};
+
+	#[test]
+	fn walk_matches_scalar_multiplication() {
+		let start = Random.generate().unwrap();
+		let mut walk = Walk::new(&start);
+		let mut publics = Vec::new();
+
+		walk.next_batch(BATCH, &mut publics).unwrap();
+		walk.next_batch(3, &mut publics).unwrap();
+		assert_eq!(walk.offset(), BATCH as u64 + 3);
+		assert_eq!(publics.len(), 3);
+
+		for (i, public) in publics.iter().enumerate() {
+			let keypair = KeyPair::from_secret(walk.secret(BATCH as u64 + i as u64 + 1).unwrap()).unwrap();
+			assert_eq!(keypair.public(), public);
+		}
+		assert_eq!(walk.secret(0).unwrap(), *start.secret());
+	}
+
+	#[test]
+	fn walk_with_base() {
+		let base = Random.generate().unwrap();
+		let start = Random.generate().unwrap();
+		let mut walk = Walk::with_base(base.public(), &start).unwrap();
+		let mut publics = Vec::new();
+		walk.next_batch(2, &mut publics).unwrap();
+
+		let mut secret = walk.secret(2).unwrap();
+		secret_add(&mut secret, base.secret()).unwrap();
+		assert_eq!(KeyPair::from_secret(secret).unwrap().public(), &publics[1]);
+	}
+
+	#[test]
+	fn incremental_generator() {
+		let keypair = Incremental::with_threads(|address| address[0] == 0xff && address[19] & 0x0f == 0, usize::max_value(), 2).generate().unwrap();
+		assert_eq!(keypair.address()[0], 0xff);
+		assert_eq!(keypair.address()[19] & 0x0f, 0);
+	}
+
+	#[test]
+	fn incremental_iterations_cap() {
+		assert!(Incremental::new(|_| false, 1000).generate().is_err());
+	}
+
+	#[test]
+	fn incremental_progress() {
+		let progress = Progress::new();
+		assert!(Incremental::with_progress(|_| false, 1000, 2, progress.clone()).generate().is_err());
+		assert_eq!(progress.attempts(), 1000);
+
+		let progress = Progress::with_timeout(Duration::from_millis(100));
+		match Incremental::with_progress(|_| false, usize::max_value(), 2, progress.clone()).generate() {
+			Err(Error::Cancelled) => (),
+			Err(err) => panic!("expected cancelled search, got {}", err),
+			Ok(_) => panic!("expected cancelled search"),
+		}
+		assert!(progress.attempts() > 0);
+	}
+
+	#[test]
+	fn resumable_search() {
+		let seed = Random.generate().unwrap().secret().clone();
+		let predicate = |address: &Address| address[0] == 0xab;
+		let checkpoints = Arc::new(Mutex::new(Vec::new()));
+		let saved = checkpoints.clone();
+		let found = run_resumable(&seed, &[0], predicate, usize::max_value(), Duration::from_millis(1), &Progress::new(), move |offsets| {
+			saved.lock().unwrap().push(offsets[0]);
+			Ok(())
+		}).unwrap();
+		assert_eq!(KeyPair::from_secret(found.clone()).unwrap().address()[0], 0xab);
+
+		// checkpoints never pass the match, resuming from the last one finds it again
+		let checkpoints = checkpoints.lock().unwrap();
+		assert!(checkpoints.windows(2).all(|w| w[0] <= w[1]));
+		let resumed = run_resumable(&seed, &[*checkpoints.last().unwrap()], predicate, usize::max_value(), Duration::from_secs(60), &Progress::new(), |_| Ok(())).unwrap();
+		assert_eq!(resumed, found);
+	}
+
+	#[test]
+	fn resumable_search_iterations_cap() {
+		let seed = Random.generate().unwrap().secret().clone();
+		let mut last = Vec::new();
+		assert!(run_resumable(&seed, &[100, 0], |_| false, 1000, Duration::from_secs(60), &Progress::new(), |offsets| {
+			last = offsets.to_vec();
+			Ok(())
+		}).is_err());
+		assert!(last[0] >= 100 && last[0] + last[1] >= 1000);
+	}
+
+	#[test]
+	#[ignore]
+	fn incremental_speedup() {
+		// cargo test --release incremental_speedup -- --ignored --nocapture
+		let duration = Duration::from_secs(5);
+		let progress = Progress::with_timeout(duration);
+		assert!(Incremental::with_progress(|_: &Address| false, usize::max_value(), 1, progress.clone()).generate().is_err());
+		let incremental = progress.attempts() as f64 / duration.as_secs() as f64;
+
+		// scalar multiplication per attempt, as `Prefix` searched before
+		let progress = Progress::with_timeout(duration);
+		let prefix = [0xffu8; 20];
+		while !progress.is_cancelled() {
+			assert!(!Random.generate().unwrap().address().starts_with(&prefix));
+			progress.counter().fetch_add(1, Ordering::Relaxed);
+		}
+		let scalar = progress.attempts() as f64 / duration.as_secs() as f64;
+
+		println!("incremental: {:.0} keys/s, scalar: {:.0} keys/s, speedup: {:.1}x", incremental, scalar, incremental / scalar);
+		assert!(incremental > 10.0 * scalar);
+	}
+}
@@ -2,6 +2,7 @@ use std::fmt;
 use secp256k1::key;
 use rustc_serialize::hex::ToHex;
 use keccak::Keccak256;
+use rlp::RlpStream;
 use super::{Secret, Public, Address, SECP256K1, Error};
 
 pub fn public_to_address(public: &Public) -> Address {
@@ -11,6 +12,30 @@ pub fn public_to_address(public: &Public) -> Address {
 	result
 }
 
+/// Address of contract created by `sender` with given nonce, `keccak256(rlp([sender, nonce]))[12..]`.
+pub fn contract_address(sender: &Address, nonce: u64) -> Address {
+	let mut stream = RlpStream::new();
+	stream.append_bytes(&sender[..]).append_uint(nonce as u128);
+	let hash = stream.out().keccak256();
+	let mut result = Address::default();
+	result.copy_from_slice(&hash[12..]);
+	result
+}
+
+/// Address of contract created by `deployer` with `CREATE2`,
+/// `keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`.
+pub fn create2_address(deployer: &Address, salt: &[u8; 32], init_code_hash: &[u8; 32]) -> Address {
+	let mut data = [0u8; 85];
+	data[0] = 0xff;
+	data[1..21].copy_from_slice(&deployer[..]);
+	data[21..53].copy_from_slice(salt);
+	data[53..85].copy_from_slice(init_code_hash);
+	let hash = data.keccak256();
+	let mut result = Address::default();
+	result.copy_from_slice(&hash[12..]);
+	result
+}
+
 /// secp256k1 key pair
 pub struct KeyPair {
 	secret: Secret,
@@ -21,7 +46,7 @@ impl fmt::Display for KeyPair {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
 		try!(writeln!(f, "secret:  {}", self.secret.to_hex()));
 		try!(writeln!(f, "public:  {}", self.public.to_hex()));
-		write!(f, "address: {}", self.address().to_hex())
+		write!(f, "address: {}", self.address().to_checksum())
 	}
 }
 
@@ -74,7 +99,10 @@ impl KeyPair {
 #[cfg(test)]
 mod tests {
 	use std::str::FromStr;
-	use {KeyPair, Secret};
+	use rustc_serialize::hex::FromHex;
+	use keccak::Keccak256;
+	use {KeyPair, Secret, Address};
+	use super::{contract_address, create2_address};
 
 	#[test]
 	fn from_secret() {
@@ -87,9 +115,39 @@ mod tests {
 		let expected =
 "secret:  a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65
 public:  8ce0db0b0359ffc5866ba61903cc2518c3675ef2cf380a7e54bde7ea20e6fa1ab45b7617346cd11b7610001ee6ae5b0155c41cad9527cbcdff44ec67848943a4
-address: 5b073e9233944b5e729e46d618f0d8edf3d9c34a".to_owned();
+address: 5b073e9233944b5E729e46D618f0D8eDF3D9C34A".to_owned();
 		let secret = Secret::from_str("a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65").unwrap();
 		let kp = KeyPair::from_secret(secret).unwrap();
 		assert_eq!(format!("{}", kp), expected);
 	}
+
+	#[test]
+	fn contract_addresses() {
+		let sender = Address::from_str("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap();
+		assert_eq!(contract_address(&sender, 0), Address::from_str("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d").unwrap());
+		assert_eq!(contract_address(&sender, 1), Address::from_str("343c43a37d37dff08ae8c4a11544c718abb4fcf8").unwrap());
+		assert_eq!(contract_address(&sender, 2), Address::from_str("f778b86fa74e846c4f0a1fbd1335fe81c00a0c91").unwrap());
+	}
+
+	#[test]
+	fn create2_addresses() {
+		// examples from EIP-1014
+		let examples = [
+			("0000000000000000000000000000000000000000", "0000000000000000000000000000000000000000000000000000000000000000", "00", "4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"),
+			("deadbeef00000000000000000000000000000000", "0000000000000000000000000000000000000000000000000000000000000000", "00", "B928f69Bb1D91Cd65274e3c79d8986362984fDA3"),
+			("deadbeef00000000000000000000000000000000", "000000000000000000000000feed000000000000000000000000000000000000", "00", "D04116cDd17beBE565EB2422F2497E06cC1C9833"),
+			("0000000000000000000000000000000000000000", "0000000000000000000000000000000000000000000000000000000000000000", "deadbeef", "70f2b2914A2a4b783FaEFb75f459A580616Fcb5e"),
+			("00000000000000000000000000000000deadbeef", "00000000000000000000000000000000000000000000000000000000cafebabe", "deadbeef", "60f3f640a8508fC6a86d45DF051962668E1e8AC7"),
+			("00000000000000000000000000000000deadbeef", "00000000000000000000000000000000000000000000000000000000cafebabe", "deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef", "1d8bfDC5D46DC4f61D6b6115972536eBE6A8854C"),
+			("0000000000000000000000000000000000000000", "0000000000000000000000000000000000000000000000000000000000000000", "", "E33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0"),
+		];
+
+		for &(deployer, salt, init_code, expected) in &examples {
+			let deployer = Address::from_str(deployer).unwrap();
+			let mut salt_bytes = [0u8; 32];
+			salt_bytes.copy_from_slice(&salt.from_hex().unwrap());
+			let init_code_hash = init_code.from_hex().unwrap().keccak256();
+			assert_eq!(create2_address(&deployer, &salt_bytes, &init_code_hash).to_checksum(), expected);
+		}
+	}
 }
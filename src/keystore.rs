@@ -0,0 +1,412 @@
+//! Web3 Secret Storage (version 3) key files.
+
+use std::fmt;
+use std::str::FromStr;
+use std::collections::BTreeMap;
+use rand::Rng;
+use rand::os::OsRng;
+use rustc_serialize::hex::{ToHex, FromHex};
+use rustc_serialize::json::{Json, ToJson};
+use crypto::aes::{ctr, KeySize};
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha256;
+use crypto::util::fixed_time_eq;
+use keccak::Keccak256;
+use scrypt::scrypt;
+use super::{KeyPair, Secret, Address, Error};
+
+const DKLEN: usize = 32;
+const CIPHER: &'static str = "aes-128-ctr";
+
+/// Key derivation function used to derive encryption key from the password.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kdf {
+	/// PBKDF2 with HMAC-SHA256.
+	Pbkdf2 {
+		/// Number of iterations.
+		c: u32,
+		salt: Vec<u8>,
+	},
+	/// Scrypt.
+	Scrypt {
+		/// CPU/memory cost, must be a power of 2.
+		n: u32,
+		/// Block size.
+		r: u32,
+		/// Parallelization.
+		p: u32,
+		salt: Vec<u8>,
+	},
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, Error> {
+	let mut rng = try!(OsRng::new());
+	let mut result = vec![0u8; len];
+	rng.fill_bytes(&mut result);
+	Ok(result)
+}
+
+impl Kdf {
+	/// PBKDF2 with given number of iterations and random salt.
+	pub fn pbkdf2(c: u32) -> Result<Self, Error> {
+		Ok(Kdf::Pbkdf2 {
+			c: c,
+			salt: try!(random_bytes(32)),
+		})
+	}
+
+	/// Scrypt with given parameters and random salt.
+	pub fn scrypt(n: u32, r: u32, p: u32) -> Result<Self, Error> {
+		Ok(Kdf::Scrypt {
+			n: n,
+			r: r,
+			p: p,
+			salt: try!(random_bytes(32)),
+		})
+	}
+
+	/// Name of the function as used in key files.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			Kdf::Pbkdf2 { .. } => "pbkdf2",
+			Kdf::Scrypt { .. } => "scrypt",
+		}
+	}
+
+	fn derive(&self, password: &str) -> Result<[u8; DKLEN], Error> {
+		let mut result = [0u8; DKLEN];
+		match *self {
+			Kdf::Pbkdf2 { c, ref salt } => {
+				if c == 0 {
+					return Err(Error::InvalidKeyFile);
+				}
+				let mut mac = Hmac::new(Sha256::new(), password.as_bytes());
+				pbkdf2(&mut mac, salt, c, &mut result);
+			},
+			Kdf::Scrypt { n, r, p, ref salt } => {
+				try!(scrypt(password.as_bytes(), salt, n, r, p, &mut result).map_err(|_| Error::InvalidKeyFile));
+			},
+		}
+		Ok(result)
+	}
+
+	fn to_json(&self) -> Json {
+		let mut params = BTreeMap::new();
+		params.insert("dklen".to_owned(), DKLEN.to_json());
+		match *self {
+			Kdf::Pbkdf2 { c, ref salt } => {
+				params.insert("c".to_owned(), c.to_json());
+				params.insert("prf".to_owned(), "hmac-sha256".to_json());
+				params.insert("salt".to_owned(), salt.to_hex().to_json());
+			},
+			Kdf::Scrypt { n, r, p, ref salt } => {
+				params.insert("n".to_owned(), n.to_json());
+				params.insert("r".to_owned(), r.to_json());
+				params.insert("p".to_owned(), p.to_json());
+				params.insert("salt".to_owned(), salt.to_hex().to_json());
+			},
+		}
+		Json::Object(params)
+	}
+
+	fn from_json(name: &str, params: &Json) -> Result<Self, Error> {
+		let number = |key: &str| params.find(key)
+			.and_then(Json::as_u64)
+			.and_then(|n| if n <= u32::max_value() as u64 { Some(n as u32) } else { None })
+			.ok_or(Error::InvalidKeyFile);
+		let salt = try!(hex_field(params, "salt"));
+
+		if params.find("dklen").and_then(Json::as_u64) != Some(DKLEN as u64) {
+			return Err(Error::InvalidKeyFile);
+		}
+
+		match name {
+			"pbkdf2" => {
+				if params.find("prf").and_then(Json::as_string) != Some("hmac-sha256") {
+					return Err(Error::InvalidKeyFile);
+				}
+				Ok(Kdf::Pbkdf2 {
+					c: try!(number("c")),
+					salt: salt,
+				})
+			},
+			"scrypt" => Ok(Kdf::Scrypt {
+				n: try!(number("n")),
+				r: try!(number("r")),
+				p: try!(number("p")),
+				salt: salt,
+			}),
+			_ => Err(Error::InvalidKeyFile),
+		}
+	}
+}
+
+fn hex_field(json: &Json, key: &str) -> Result<Vec<u8>, Error> {
+	json.find(key)
+		.and_then(Json::as_string)
+		.and_then(|s| s.trim_left_matches("0x").from_hex().ok())
+		.ok_or(Error::InvalidKeyFile)
+}
+
+fn random_uuid() -> Result<String, Error> {
+	let mut id = try!(random_bytes(16));
+	// version 4, variant 1
+	id[6] = (id[6] & 0x0f) | 0x40;
+	id[8] = (id[8] & 0x3f) | 0x80;
+	Ok(format!("{}-{}-{}-{}-{}", id[0..4].to_hex(), id[4..6].to_hex(), id[6..8].to_hex(), id[8..10].to_hex(), id[10..16].to_hex()))
+}
+
+fn mac(derived: &[u8; DKLEN], ciphertext: &[u8]) -> [u8; 32] {
+	let mut data = derived[16..32].to_vec();
+	data.extend_from_slice(ciphertext);
+	data.keccak256()
+}
+
+fn aes_128_ctr(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
+	let mut result = vec![0u8; data.len()];
+	ctr(KeySize::KeySize128, key, iv).process(data, &mut result);
+	result
+}
+
+/// Secret encrypted with a password, serializable to Web3 Secret Storage json.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyFile {
+	/// Key file identifier, usually a UUID.
+	pub id: String,
+	/// Address of the key. Not part of the standard, but stored by both geth and Parity.
+	pub address: Option<Address>,
+	pub kdf: Kdf,
+	pub iv: Vec<u8>,
+	pub ciphertext: Vec<u8>,
+	pub mac: [u8; 32],
+}
+
+impl KeyFile {
+	/// Encrypts secret with the password.
+	pub fn encrypt(secret: &Secret, password: &str, kdf: Kdf) -> Result<Self, Error> {
+		let keypair = try!(KeyPair::from_secret(secret.clone()));
+		let derived = try!(kdf.derive(password));
+		let iv = try!(random_bytes(16));
+		let ciphertext = aes_128_ctr(&derived[0..16], &iv, &secret[..]);
+
+		Ok(KeyFile {
+			id: try!(random_uuid()),
+			address: Some(keypair.address()),
+			kdf: kdf,
+			mac: mac(&derived, &ciphertext),
+			iv: iv,
+			ciphertext: ciphertext,
+		})
+	}
+
+	/// Decrypts secret. Returns `Error::InvalidPassword` if the MAC doesn't match.
+	pub fn decrypt(&self, password: &str) -> Result<Secret, Error> {
+		if self.iv.len() != 16 || self.ciphertext.len() != 32 {
+			return Err(Error::InvalidKeyFile);
+		}
+
+		let derived = try!(self.kdf.derive(password));
+		// constant time comparison
+		if !fixed_time_eq(&mac(&derived, &self.ciphertext), &self.mac) {
+			return Err(Error::InvalidPassword);
+		}
+
+		let mut secret = Secret::default();
+		secret.copy_from_slice(&aes_128_ctr(&derived[0..16], &self.iv, &self.ciphertext));
+		Ok(secret)
+	}
+}
+
+impl ToJson for KeyFile {
+	fn to_json(&self) -> Json {
+		let mut cipherparams = BTreeMap::new();
+		cipherparams.insert("iv".to_owned(), self.iv.to_hex().to_json());
+
+		let mut crypto = BTreeMap::new();
+		crypto.insert("cipher".to_owned(), CIPHER.to_json());
+		crypto.insert("cipherparams".to_owned(), Json::Object(cipherparams));
+		crypto.insert("ciphertext".to_owned(), self.ciphertext.to_hex().to_json());
+		crypto.insert("kdf".to_owned(), self.kdf.name().to_json());
+		crypto.insert("kdfparams".to_owned(), self.kdf.to_json());
+		crypto.insert("mac".to_owned(), self.mac.to_hex().to_json());
+
+		let mut file = BTreeMap::new();
+		if let Some(ref address) = self.address {
+			file.insert("address".to_owned(), address.to_hex().to_json());
+		}
+		file.insert("crypto".to_owned(), Json::Object(crypto));
+		file.insert("id".to_owned(), self.id.to_json());
+		file.insert("version".to_owned(), 3.to_json());
+		Json::Object(file)
+	}
+}
+
+impl fmt::Display for KeyFile {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{}", self.to_json())
+	}
+}
+
+impl FromStr for KeyFile {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let json = try!(Json::from_str(s).map_err(|_| Error::InvalidKeyFile));
+		if json.find("version").and_then(Json::as_u64) != Some(3) {
+			return Err(Error::InvalidKeyFile);
+		}
+
+		// some wallets capitalize the crypto key
+		let crypto = try!(json.find("crypto").or_else(|| json.find("Crypto")).ok_or(Error::InvalidKeyFile));
+		if crypto.find("cipher").and_then(Json::as_string) != Some(CIPHER) {
+			return Err(Error::InvalidKeyFile);
+		}
+
+		let kdf_name = try!(crypto.find("kdf").and_then(Json::as_string).ok_or(Error::InvalidKeyFile));
+		let kdf_params = try!(crypto.find("kdfparams").ok_or(Error::InvalidKeyFile));
+		let iv = try!(crypto.find("cipherparams").ok_or(Error::InvalidKeyFile).and_then(|params| hex_field(params, "iv")));
+		let mac_bytes = try!(hex_field(crypto, "mac"));
+		if mac_bytes.len() != 32 {
+			return Err(Error::InvalidKeyFile);
+		}
+		let mut mac = [0u8; 32];
+		mac.copy_from_slice(&mac_bytes);
+
+		let address = match json.find("address").and_then(Json::as_string) {
+			Some(address) => Some(try!(Address::from_str(address.trim_left_matches("0x")))),
+			None => None,
+		};
+
+		Ok(KeyFile {
+			id: json.find("id").and_then(Json::as_string).unwrap_or("").to_owned(),
+			address: address,
+			kdf: try!(Kdf::from_json(kdf_name, kdf_params)),
+			iv: iv,
+			ciphertext: try!(hex_field(crypto, "ciphertext")),
+			mac: mac,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use {Generator, Random, Error};
+	use super::{KeyFile, Kdf};
+
+	#[test]
+	fn decrypt_pbkdf2_test_vector() {
+		let json = r#"{
+			"crypto": {
+				"cipher": "aes-128-ctr",
+				"cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+				"ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+				"kdf": "pbkdf2",
+				"kdfparams": {
+					"c": 262144,
+					"dklen": 32,
+					"prf": "hmac-sha256",
+					"salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+				},
+				"mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+			},
+			"id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+			"version": 3
+		}"#;
+
+		let file = KeyFile::from_str(json).unwrap();
+		assert_eq!(file.id, "3198bc9c-6672-5ab3-d995-4942343ae5b6");
+		assert_eq!(file.decrypt("testpassword").unwrap().to_string(), "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d");
+	}
+
+	#[test]
+	fn decrypt_scrypt_test_vector() {
+		let json = r#"{
+			"crypto": {
+				"cipher": "aes-128-ctr",
+				"cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+				"ciphertext": "d172bf743a674da9cdad04534d56926ef8358534d458fffccd4e6ad2fbde479c",
+				"kdf": "scrypt",
+				"kdfparams": {
+					"dklen": 32,
+					"n": 262144,
+					"p": 8,
+					"r": 1,
+					"salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+				},
+				"mac": "2103ac29920d71da29f15d75b4a16dbe95cfd7ff8faea1056c33131d846e3097"
+			},
+			"id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+			"version": 3
+		}"#;
+
+		let file = KeyFile::from_str(json).unwrap();
+		assert_eq!(file.decrypt("testpassword").unwrap().to_string(), "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d");
+	}
+
+	#[test]
+	fn encrypt_and_decrypt_scrypt() {
+		let keypair = Random.generate().unwrap();
+		let kdf = Kdf::scrypt(1024, 8, 1).unwrap();
+		let file = KeyFile::encrypt(keypair.secret(), "password", kdf).unwrap();
+		let file = KeyFile::from_str(&file.to_string()).unwrap();
+
+		assert_eq!(file.address, Some(keypair.address()));
+		assert_eq!(&file.decrypt("password").unwrap(), keypair.secret());
+		match file.decrypt("wrong password") {
+			Err(Error::InvalidPassword) => (),
+			other => panic!("expected invalid password error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn encrypt_and_decrypt_pbkdf2() {
+		let keypair = Random.generate().unwrap();
+		let file = KeyFile::encrypt(keypair.secret(), "password", Kdf::pbkdf2(1024).unwrap()).unwrap();
+		let file = KeyFile::from_str(&file.to_string()).unwrap();
+		assert_eq!(&file.decrypt("password").unwrap(), keypair.secret());
+	}
+
+	#[test]
+	fn reject_invalid_scrypt_params() {
+		let keypair = Random.generate().unwrap();
+		let file = KeyFile::encrypt(keypair.secret(), "password", Kdf::pbkdf2(1).unwrap()).unwrap();
+		let broken = KeyFile {
+			kdf: Kdf::Scrypt { n: 1000, r: 8, p: 1, salt: vec![] },
+			..file
+		};
+		match broken.decrypt("password") {
+			Err(Error::InvalidKeyFile) => (),
+			other => panic!("expected invalid key file error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn reject_oversized_scrypt_params() {
+		let json = r#"{
+			"crypto": {
+				"cipher": "aes-128-ctr",
+				"cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+				"ciphertext": "d172bf743a674da9cdad04534d56926ef8358534d458fffccd4e6ad2fbde479c",
+				"kdf": "scrypt",
+				"kdfparams": {
+					"dklen": 32,
+					"n": 2147483648,
+					"p": 1,
+					"r": 1048576,
+					"salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+				},
+				"mac": "2103ac29920d71da29f15d75b4a16dbe95cfd7ff8faea1056c33131d846e3097"
+			},
+			"id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+			"version": 3
+		}"#;
+
+		let file = KeyFile::from_str(json).unwrap();
+		match file.decrypt("testpassword") {
+			Err(Error::InvalidKeyFile) => (),
+			other => panic!("expected invalid key file error, got {:?}", other),
+		}
+	}
+}
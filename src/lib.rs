@@ -4,15 +4,41 @@ extern crate lazy_static;
 extern crate tiny_keccak;
 extern crate secp256k1;
 extern crate rustc_serialize;
+extern crate crypto;
+extern crate regex;
 
+mod argon2;
+mod base58;
 mod brain;
+mod brain_prefix;
+mod brain_recovery;
+mod checkpoint;
+mod deployer;
+mod ecies;
 mod error;
+mod extended;
+mod field;
+mod incremental;
 mod keypair;
+mod keystore;
 mod keccak;
+mod matcher;
+mod math;
+mod mnemonic;
+mod personal;
 mod prefix;
 mod primitive;
+mod progress;
 mod random;
+mod rlp;
+mod salt;
+mod score;
+mod secret_recovery;
+mod scrypt;
 mod signature;
+mod transaction;
+mod typed_data;
+mod vanity;
 
 lazy_static! {
 	static ref SECP256K1: secp256k1::Secp256k1 = secp256k1::Secp256k1::new();
@@ -24,10 +50,30 @@ pub trait Generator {
 	fn generate(self) -> Result<KeyPair, Error>;
 }
 
-pub use self::brain::Brain;
+pub use self::brain::{Brain, BrainPhrase, BrainKdf, HardenedBrain};
+pub use self::brain_prefix::BrainPrefix;
+pub use self::brain_recovery::{BrainRecovery, BrainCandidates, brain_candidates};
+pub use self::checkpoint::{Checkpoint, SearchSeed};
+pub use self::deployer::Deployer;
+pub use self::ecies::{encrypt, decrypt};
 pub use self::error::Error;
-pub use self::keypair::{KeyPair, public_to_address};
+pub use self::extended::{ExtendedSecret, ExtendedPublic, Derivation, DerivationPath};
+pub use self::incremental::{Incremental, Walk};
+pub use self::keccak::Keccak256;
+pub use self::keypair::{KeyPair, public_to_address, contract_address, create2_address};
+pub use self::keystore::{KeyFile, Kdf};
+pub use self::matcher::Matcher;
+pub use self::mnemonic::{Mnemonic, random_phrase, phrase_from_entropy, phrase_to_entropy, phrase_to_seed};
+pub use self::personal::{personal_message, validator_message, sign_personal, recover_personal};
 pub use self::primitive::{Secret, Public, Address, Message};
+pub use self::progress::Progress;
 pub use self::prefix::Prefix;
 pub use self::random::Random;
+pub use self::rlp::{RlpStream, Rlp};
+pub use self::salt::{SaltMiner, SaltTarget};
+pub use self::score::{Score, Scoring};
+pub use self::secret_recovery::{SecretTemplate, SecretRecovery};
 pub use self::signature::{sign, verify_public, verify_address, recover, Signature};
+pub use self::transaction::{Transaction, TransactionType, AccessListItem, SignedTransaction, recover_sender};
+pub use self::typed_data::{TypedData, sign_typed_data, recover_typed_data};
+pub use self::vanity::{Vanity, VanityWork, vanity_address, vanity_combine};
@@ -0,0 +1,261 @@
+//! Address patterns for vanity search.
+
+use std::str::FromStr;
+use regex::Regex;
+use rustc_serialize::hex::ToHex;
+use super::{Address, Error};
+
+/// Number of hex characters in the address.
+const NIBBLES: usize = 40;
+
+fn nibble(address: &Address, index: usize) -> u8 {
+	match index % 2 {
+		0 => address[index / 2] >> 4,
+		_ => address[index / 2] & 0x0f,
+	}
+}
+
+/// Hex pattern, `None` nibbles match anything. Letters may require checksum case.
+#[derive(Debug, Clone, PartialEq)]
+struct Nibbles {
+	nibbles: Vec<Option<u8>>,
+	uppercase: Vec<Option<bool>>,
+}
+
+impl FromStr for Nibbles {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut nibbles = Vec::with_capacity(s.len());
+		let mut uppercase = Vec::with_capacity(s.len());
+		for c in s.chars() {
+			match c {
+				'?' => {
+					nibbles.push(None);
+					uppercase.push(None);
+				},
+				'0'..='9' => {
+					nibbles.push(c.to_digit(16).map(|n| n as u8));
+					uppercase.push(None);
+				},
+				'a'..='f' | 'A'..='F' => {
+					nibbles.push(c.to_digit(16).map(|n| n as u8));
+					uppercase.push(Some(c.is_uppercase()));
+				},
+				_ => return Err(Error::InvalidPattern(format!("invalid character {:?}", c))),
+			}
+		}
+
+		if nibbles.len() > NIBBLES {
+			return Err(Error::InvalidPattern("pattern is longer than address".into()));
+		}
+
+		Ok(Nibbles {
+			nibbles: nibbles,
+			uppercase: uppercase,
+		})
+	}
+}
+
+impl Nibbles {
+	fn len(&self) -> usize {
+		self.nibbles.len()
+	}
+
+	fn has_uppercase(&self) -> bool {
+		self.uppercase.iter().any(|c| *c == Some(true))
+	}
+
+	fn fixed(&self) -> usize {
+		self.nibbles.iter().filter(|n| n.is_some()).count()
+	}
+
+	fn letters(&self) -> usize {
+		self.uppercase.iter().filter(|c| c.is_some()).count()
+	}
+
+	fn matches_at(&self, address: &Address, offset: usize) -> bool {
+		self.nibbles.iter().enumerate().all(|(i, n)| n.map_or(true, |n| nibble(address, offset + i) == n))
+	}
+
+	fn case_matches_at(&self, checksum: &[u8], offset: usize) -> bool {
+		self.uppercase.iter().enumerate().all(|(i, c)| c.map_or(true, |c| (checksum[offset + i] as char).is_uppercase() == c))
+	}
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+	/// Nibbles at the beginning and at the end of the address.
+	Affix(Nibbles, Nibbles),
+	/// Nibbles anywhere in the address.
+	Contains(Nibbles),
+	/// Regular expression over lowercase hex address.
+	Regex(Regex),
+}
+
+/// Address pattern.
+///
+/// - `dead`, `0xdead` - address starts with given nibbles
+/// - `dead...beef`, `...beef` - address starts and/or ends with given nibbles
+/// - `contains:cafe` - address contains given nibbles
+/// - `regex:^0{6}|^f{6}` - regular expression matches lowercase hex address
+///
+/// `?` matches any nibble. Hex patterns with uppercase letters must also match EIP-55 checksum case.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+	kind: Kind,
+	checksum: bool,
+}
+
+impl FromStr for Matcher {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s.starts_with("regex:") {
+			let regex = try!(Regex::new(&s[6..]).map_err(|e| Error::InvalidPattern(format!("{}", e))));
+			return Ok(Matcher {
+				kind: Kind::Regex(regex),
+				checksum: false,
+			});
+		}
+
+		let kind = if s.starts_with("contains:") {
+			Kind::Contains(try!(Nibbles::from_str(&s[9..])))
+		} else {
+			let s = if s.starts_with("0x") { &s[2..] } else { s };
+			let (prefix, suffix) = match s.find("...") {
+				Some(position) => (&s[..position], &s[position + 3..]),
+				None => (s, ""),
+			};
+			let (prefix, suffix) = (try!(Nibbles::from_str(prefix)), try!(Nibbles::from_str(suffix)));
+			if prefix.len() + suffix.len() > NIBBLES {
+				return Err(Error::InvalidPattern("pattern is longer than address".into()));
+			}
+			Kind::Affix(prefix, suffix)
+		};
+
+		let checksum = match kind {
+			Kind::Affix(ref prefix, ref suffix) => prefix.has_uppercase() || suffix.has_uppercase(),
+			Kind::Contains(ref nibbles) => nibbles.has_uppercase(),
+			Kind::Regex(_) => false,
+		};
+
+		Ok(Matcher {
+			kind: kind,
+			checksum: checksum,
+		})
+	}
+}
+
+impl Matcher {
+	/// Returns true if the address matches the pattern.
+	pub fn is_match(&self, address: &Address) -> bool {
+		match self.kind {
+			Kind::Affix(ref prefix, ref suffix) => {
+				let suffix_offset = NIBBLES - suffix.len();
+				if !prefix.matches_at(address, 0) || !suffix.matches_at(address, suffix_offset) {
+					return false;
+				}
+				if !self.checksum {
+					return true;
+				}
+				let checksum = address.to_checksum();
+				prefix.case_matches_at(checksum.as_bytes(), 0) && suffix.case_matches_at(checksum.as_bytes(), suffix_offset)
+			},
+			Kind::Contains(ref nibbles) => {
+				let mut checksum = None;
+				(0..NIBBLES - nibbles.len() + 1).any(|offset| {
+					nibbles.matches_at(address, offset) && (!self.checksum || {
+						let checksum = checksum.get_or_insert_with(|| address.to_checksum());
+						nibbles.case_matches_at(checksum.as_bytes(), offset)
+					})
+				})
+			},
+			Kind::Regex(ref regex) => regex.is_match(&address.to_hex()),
+		}
+	}
+
+	/// Returns expected number of attempts to find matching address, `None` if unknown.
+	pub fn difficulty(&self) -> Option<f64> {
+		let difficulty = |nibbles: &Nibbles| {
+			let case = if self.checksum { 2f64.powi(nibbles.letters() as i32) } else { 1.0 };
+			16f64.powi(nibbles.fixed() as i32) * case
+		};
+
+		match self.kind {
+			Kind::Affix(ref prefix, ref suffix) => Some(difficulty(prefix) * difficulty(suffix)),
+			Kind::Contains(ref nibbles) => Some(difficulty(nibbles) / (NIBBLES - nibbles.len() + 1) as f64),
+			Kind::Regex(_) => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use Address;
+	use super::Matcher;
+
+	fn matches(pattern: &str, address: &str) -> bool {
+		Matcher::from_str(pattern).unwrap().is_match(&Address::from_str(address).unwrap())
+	}
+
+	#[test]
+	fn affix_patterns() {
+		let address = "dead5b0e0b5c2e7a0e2d4a1c6a9d0a1b2c3dbeef";
+		assert!(matches("dead", address));
+		assert!(matches("0xdea", address));
+		assert!(matches("dead...beef", address));
+		assert!(matches("...eef", address));
+		assert!(matches("de??5b", address));
+		assert!(!matches("deaf", address));
+		assert!(!matches("dead...bee", address));
+		assert!(matches("", address));
+	}
+
+	#[test]
+	fn contains_pattern() {
+		let address = "dead5b0e0b5c2e7a0e2d4a1c6a9d0a1b2c3dbeef";
+		assert!(matches("contains:2e7a", address));
+		assert!(matches("contains:c?e7", address));
+		assert!(matches("contains:beef", address));
+		assert!(!matches("contains:cafe", address));
+	}
+
+	#[test]
+	fn regex_pattern() {
+		let address = "dead5b0e0b5c2e7a0e2d4a1c6a9d0a1b2c3dbeef";
+		assert!(matches("regex:^dead.*beef$", address));
+		assert!(matches("regex:(e|f)0b", address));
+		assert!(!matches("regex:^beef", address));
+		assert!(Matcher::from_str("regex:(").is_err());
+	}
+
+	#[test]
+	fn checksum_case_patterns() {
+		// checksum encoding is 5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed
+		let address = "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+		assert!(matches("5aA", address));
+		assert!(!matches("5AA", address));
+		assert!(matches("5aa", address));
+		assert!(matches("...BeAed", address));
+		assert!(!matches("...beaED", address));
+		assert!(matches("contains:3F3E", address));
+		assert!(!matches("contains:3f3E", address));
+	}
+
+	#[test]
+	fn invalid_patterns() {
+		assert!(Matcher::from_str("xyz").is_err());
+		assert!(Matcher::from_str(&"0".repeat(41)).is_err());
+		assert!(Matcher::from_str(&format!("{}...{}", "0".repeat(20), "0".repeat(21))).is_err());
+	}
+
+	#[test]
+	fn difficulty() {
+		assert_eq!(Matcher::from_str("dead").unwrap().difficulty(), Some(65536.0));
+		assert_eq!(Matcher::from_str("de??").unwrap().difficulty(), Some(256.0));
+		assert_eq!(Matcher::from_str("dE").unwrap().difficulty(), Some(1024.0));
+		assert_eq!(Matcher::from_str("regex:^00").unwrap().difficulty(), None);
+	}
+}
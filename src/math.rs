@@ -0,0 +1,87 @@
+use secp256k1::key;
+use super::{Secret, Public, SECP256K1, Error};
+
+fn to_secp256k1_public(public: &Public) -> Result<key::PublicKey, Error> {
+	let context = &SECP256K1;
+	let pdata = {
+		let mut temp = [4u8; 65];
+		temp[1..65].copy_from_slice(&public[..]);
+		temp
+	};
+
+	Ok(try!(key::PublicKey::from_slice(context, &pdata)))
+}
+
+fn set_public(public: &mut Public, key_public: &key::PublicKey) {
+	let context = &SECP256K1;
+	let serialized = key_public.serialize_vec(context, false);
+	public.copy_from_slice(&serialized[1..65]);
+}
+
+/// Adds `other` to `secret` modulo the curve order.
+pub fn secret_add(secret: &mut Secret, other: &Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_secret = try!(key::SecretKey::from_slice(context, &secret[..]));
+	let other_secret = try!(key::SecretKey::from_slice(context, &other[..]));
+	try!(key_secret.add_assign(context, &other_secret));
+	secret.copy_from_slice(&key_secret[0..32]);
+	Ok(())
+}
+
+/// Adds `secret * G` to the `public`.
+pub fn public_add_secret(public: &mut Public, secret: &Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_public = try!(to_secp256k1_public(public));
+	let key_secret = try!(key::SecretKey::from_slice(context, &secret[..]));
+	try!(key_public.add_exp_assign(context, &key_secret));
+	set_public(public, &key_public);
+	Ok(())
+}
+
+/// Serializes public in 33 bytes compressed form.
+pub fn public_compress(public: &Public) -> Result<[u8; 33], Error> {
+	let context = &SECP256K1;
+	let key_public = try!(to_secp256k1_public(public));
+	let serialized = key_public.serialize_vec(context, true);
+	let mut result = [0u8; 33];
+	result.copy_from_slice(&serialized[0..33]);
+	Ok(result)
+}
+
+/// Restores public from its 33 bytes compressed form.
+pub fn public_decompress(data: &[u8]) -> Result<Public, Error> {
+	let context = &SECP256K1;
+	if data.len() != 33 {
+		return Err(Error::InvalidPublic);
+	}
+	let key_public = try!(key::PublicKey::from_slice(context, data));
+	let mut public = Public::default();
+	set_public(&mut public, &key_public);
+	Ok(public)
+}
+
+#[cfg(test)]
+mod tests {
+	use {Generator, Random, KeyPair};
+	use super::{secret_add, public_add_secret, public_compress, public_decompress};
+
+	#[test]
+	fn secret_and_public_addition_match() {
+		let first = Random.generate().unwrap();
+		let second = Random.generate().unwrap();
+
+		let mut secret = first.secret().clone();
+		secret_add(&mut secret, second.secret()).unwrap();
+		let mut public = first.public().clone();
+		public_add_secret(&mut public, second.secret()).unwrap();
+
+		assert_eq!(KeyPair::from_secret(secret).unwrap().public(), &public);
+	}
+
+	#[test]
+	fn compress_roundtrip() {
+		let keypair = Random.generate().unwrap();
+		let compressed = public_compress(keypair.public()).unwrap();
+		assert_eq!(&public_decompress(&compressed).unwrap(), keypair.public());
+	}
+}
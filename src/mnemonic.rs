@@ -0,0 +1,207 @@
+//! BIP39 mnemonic phrases.
+
+use std::str::FromStr;
+use rand::Rng;
+use rand::os::OsRng;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::{Sha256, Sha512};
+use super::{Generator, KeyPair, Error, ExtendedSecret, DerivationPath};
+
+const PBKDF2_ROUNDS: u32 = 2048;
+
+/// Default derivation path of the first Ethereum account.
+pub const DEFAULT_PATH: &'static str = "m/44'/60'/0'/0/0";
+
+lazy_static! {
+	/// BIP39 English wordlist.
+	pub static ref ENGLISH: Vec<&'static str> = include_str!("../res/bip39-english.txt").lines().collect();
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+	let mut result = [0u8; 32];
+	let mut hasher = Sha256::new();
+	hasher.input(data);
+	hasher.result(&mut result);
+	result
+}
+
+fn bit(data: &[u8], index: usize) -> bool {
+	data[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
+/// Returns English mnemonic phrase encoding given entropy.
+/// Entropy must be 16, 20, 24, 28 or 32 bytes long.
+pub fn phrase_from_entropy(entropy: &[u8]) -> Result<String, Error> {
+	match entropy.len() {
+		16 | 20 | 24 | 28 | 32 => (),
+		_ => return Err(Error::InvalidMnemonic),
+	}
+
+	let checksum = sha256(entropy);
+	let entropy_bits = entropy.len() * 8;
+	let words = (entropy_bits + entropy_bits / 32) / 11;
+
+	let phrase = (0..words)
+		.map(|word| {
+			let index = (0..11).fold(0, |index, i| {
+				let position = word * 11 + i;
+				let set = match position < entropy_bits {
+					true => bit(entropy, position),
+					false => bit(&checksum, position - entropy_bits),
+				};
+				index << 1 | set as usize
+			});
+			ENGLISH[index]
+		})
+		.collect::<Vec<_>>();
+
+	Ok(phrase.join(" "))
+}
+
+/// Returns entropy encoded by the phrase. Fails if phrase contains unknown words
+/// or its checksum doesn't match.
+pub fn phrase_to_entropy(phrase: &str) -> Result<Vec<u8>, Error> {
+	let indexes = try!(phrase.split_whitespace()
+		.map(|word| ENGLISH.binary_search(&word).map_err(|_| Error::InvalidMnemonic))
+		.collect::<Result<Vec<_>, _>>());
+
+	match indexes.len() {
+		12 | 15 | 18 | 21 | 24 => (),
+		_ => return Err(Error::InvalidMnemonic),
+	}
+
+	let total_bits = indexes.len() * 11;
+	let checksum_bits = total_bits / 33;
+	let entropy_bits = total_bits - checksum_bits;
+
+	let mut data = vec![0u8; (total_bits + 7) / 8];
+	for (word, index) in indexes.iter().enumerate() {
+		for i in 0..11 {
+			if index & (1 << (10 - i)) != 0 {
+				let position = word * 11 + i;
+				data[position / 8] |= 0x80 >> (position % 8);
+			}
+		}
+	}
+
+	let entropy = data[..entropy_bits / 8].to_vec();
+	let checksum = sha256(&entropy);
+	if (0..checksum_bits).any(|i| bit(&data, entropy_bits + i) != bit(&checksum, i)) {
+		return Err(Error::InvalidMnemonic);
+	}
+
+	Ok(entropy)
+}
+
+/// Generates new phrase with given number of words from OS entropy.
+/// Number of words must be 12, 15, 18, 21 or 24.
+pub fn random_phrase(words: usize) -> Result<String, Error> {
+	match words {
+		12 | 15 | 18 | 21 | 24 => (),
+		_ => return Err(Error::InvalidMnemonic),
+	}
+
+	let mut rng = try!(OsRng::new());
+	let mut entropy = vec![0u8; words / 3 * 4];
+	rng.fill_bytes(&mut entropy);
+	phrase_from_entropy(&entropy)
+}
+
+/// Computes 64 bytes seed of the phrase. The phrase is not validated.
+/// Passphrase is used as given, without unicode normalization.
+pub fn phrase_to_seed(phrase: &str, passphrase: &str) -> [u8; 64] {
+	let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+	let salt = format!("mnemonic{}", passphrase);
+	let mut mac = Hmac::new(Sha512::new(), phrase.as_bytes());
+	let mut seed = [0u8; 64];
+	pbkdf2(&mut mac, salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+	seed
+}
+
+/// BIP39 mnemonic phrase wallet.
+pub struct Mnemonic {
+	phrase: String,
+	passphrase: String,
+	path: DerivationPath,
+}
+
+impl Mnemonic {
+	/// Creates wallet for the first account of the phrase.
+	pub fn new(phrase: String, passphrase: String) -> Self {
+		let path = DerivationPath::from_str(DEFAULT_PATH).expect("default path is valid; qed");
+		Mnemonic::with_path(phrase, passphrase, path)
+	}
+
+	/// Creates wallet for the key at given derivation path.
+	pub fn with_path(phrase: String, passphrase: String, path: DerivationPath) -> Self {
+		Mnemonic {
+			phrase: phrase,
+			passphrase: passphrase,
+			path: path,
+		}
+	}
+}
+
+impl Generator for Mnemonic {
+	fn generate(self) -> Result<KeyPair, Error> {
+		try!(phrase_to_entropy(&self.phrase));
+		let seed = phrase_to_seed(&self.phrase, &self.passphrase);
+		try!(try!(ExtendedSecret::from_seed(&seed)).derive_path(&self.path)).generate()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use {Generator, DerivationPath};
+	use super::{Mnemonic, phrase_from_entropy, phrase_to_entropy, phrase_to_seed, random_phrase};
+
+	fn check_vector(entropy: &str, phrase: &str, seed: &str) {
+		let entropy = entropy.from_hex().unwrap();
+		assert_eq!(phrase_from_entropy(&entropy).unwrap(), phrase);
+		assert_eq!(phrase_to_entropy(phrase).unwrap(), entropy);
+		assert_eq!(phrase_to_seed(phrase, "TREZOR").to_hex(), seed);
+	}
+
+	#[test]
+	fn bip39_test_vectors() {
+		check_vector("00000000000000000000000000000000",
+			"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+			"c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+		check_vector("7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+			"legal winner thank year wave sausage worth useful legal winner thank yellow",
+			"2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607");
+		check_vector("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+			"zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+			"dd48c104698c30cfe2b6142103248622fb7bb0ff692eebb00089b32d22484e1613912f0a5b694407be899ffd31ed3992c456cdf60f5d4564b8ba3f05a69890ad");
+	}
+
+	#[test]
+	fn invalid_phrase() {
+		assert!(phrase_to_entropy("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon").is_err());
+		assert!(phrase_to_entropy("abandon abandon abandon").is_err());
+		assert!(phrase_to_entropy("sparta abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").is_err());
+	}
+
+	#[test]
+	fn random_phrase_is_valid() {
+		let phrase = random_phrase(24).unwrap();
+		assert_eq!(phrase.split(' ').count(), 24);
+		assert!(phrase_to_entropy(&phrase).is_ok());
+		assert!(random_phrase(13).is_err());
+	}
+
+	#[test]
+	fn metamask_address() {
+		let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about".to_owned();
+		let keypair = Mnemonic::new(phrase.clone(), "".into()).generate().unwrap();
+		assert_eq!(keypair.address().to_hex(), "9858effd232b4033e47d90003d41ec34ecaeda94");
+
+		let path = DerivationPath::from_str("m/44'/60'/0'/0/1").unwrap();
+		let keypair = Mnemonic::with_path(phrase, "".into(), path).generate().unwrap();
+		assert_eq!(keypair.address().to_hex(), "6fac4d18c912343bf86fa7049364dd4e424ab9c0");
+	}
+}
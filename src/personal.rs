@@ -0,0 +1,73 @@
+//! EIP-191 signed data.
+
+use keccak::Keccak256;
+use super::{Secret, Public, Address, Message, Signature, Error, sign, recover};
+
+/// Hash of `"\x19Ethereum Signed Message:\n" + len(data) + data`,
+/// as signed by `personal_sign` and `eth_sign`.
+pub fn personal_message(data: &[u8]) -> Message {
+	let mut message = format!("\x19Ethereum Signed Message:\n{}", data.len()).into_bytes();
+	message.extend_from_slice(data);
+	Message::from(message.keccak256())
+}
+
+/// Hash of `0x19 || 0x00 || validator || data`, data with intended validator.
+pub fn validator_message(validator: &Address, data: &[u8]) -> Message {
+	let mut message = vec![0x19, 0x00];
+	message.extend_from_slice(&validator[..]);
+	message.extend_from_slice(data);
+	Message::from(message.keccak256())
+}
+
+/// Signs personal message. Use `Signature::into_electrum` to get
+/// signature with `v` equal to 27 or 28, as returned by wallets.
+pub fn sign_personal(secret: &Secret, data: &[u8]) -> Result<Signature, Error> {
+	sign(secret, &personal_message(data))
+}
+
+/// Recovers public of the personal message signer.
+pub fn recover_personal(signature: &Signature, data: &[u8]) -> Result<Public, Error> {
+	recover(signature, &personal_message(data))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use {KeyPair, Secret, Address, Signature, public_to_address};
+	use super::{personal_message, validator_message, sign_personal, recover_personal};
+
+	#[test]
+	fn personal_message_hash() {
+		assert_eq!(personal_message(b"hello world").to_hex(), "d9eba16ed0ecae432b71fe008c98cc872bb4cc214d3220a36f365326cf807d68");
+	}
+
+	#[test]
+	fn validator_message_hash() {
+		let validator = Address::from_str("26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5").unwrap();
+		let mut expected = vec![0x19, 0x00];
+		expected.extend_from_slice(&validator[..]);
+		expected.extend_from_slice(b"data");
+		assert_eq!(validator_message(&validator, b"data").to_hex(), ::keccak::Keccak256::keccak256(&expected[..]).to_hex());
+	}
+
+	#[test]
+	fn sign_personal_matches_web3() {
+		let secret = Secret::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+		let signature = sign_personal(&secret, b"Some data").unwrap();
+		assert_eq!(signature.into_electrum().to_hex(), "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c");
+	}
+
+	#[test]
+	fn sign_and_recover_personal() {
+		let keypair = KeyPair::from_secret(Secret::from_str("17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55").unwrap()).unwrap();
+		let signature = sign_personal(keypair.secret(), b"hello world").unwrap();
+		let electrum = signature.clone().into_electrum();
+		assert!(electrum[64] == 27 || electrum[64] == 28);
+
+		let signature = Signature::from_electrum(&electrum);
+		let public = recover_personal(&signature, b"hello world").unwrap();
+		assert_eq!(public_to_address(&public), keypair.address());
+		assert!(recover_personal(&Signature::from_electrum(&"00".from_hex().unwrap()), b"hello world").is_err());
+	}
+}
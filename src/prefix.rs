@@ -1,36 +1,68 @@
-use super::{Random, Generator, KeyPair, Error};
+use std::time::Duration;
+use incremental::run_resumable;
+use super::{Incremental, Checkpoint, Generator, Progress, KeyPair, Secret, Address, Error};
+
+/// Returns expected number of attempts to find address starting with the prefix.
+pub fn prefix_difficulty(prefix: &[u8]) -> f64 {
+	256f64.powi(prefix.len() as i32)
+}
 
 /// Tries to find keypair with address starting with given prefix.
 pub struct Prefix {
 	prefix: Vec<u8>,
 	iterations: usize,
+	threads: usize,
+	progress: Progress,
 }
 
 impl Prefix {
 	pub fn new(prefix: Vec<u8>, iterations: usize) -> Self {
+		Prefix::with_threads(prefix, iterations, 1)
+	}
+
+	pub fn with_threads(prefix: Vec<u8>, iterations: usize, threads: usize) -> Self {
+		Prefix::with_progress(prefix, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(prefix: Vec<u8>, iterations: usize, threads: usize, progress: Progress) -> Self {
 		Prefix {
 			prefix: prefix,
 			iterations: iterations,
+			threads: ::std::cmp::max(threads, 1),
+			progress: progress,
 		}
 	}
+
+	/// Returns expected number of attempts to find matching address.
+	pub fn difficulty(&self) -> f64 {
+		prefix_difficulty(&self.prefix)
+	}
+
+	/// Resumes search from the checkpoint with worker for each of its offsets. `seed` is the decrypted checkpoint seed.
+	/// Calls `save` with updated checkpoint every `interval` and once more when the search stops.
+	pub fn resume<C>(checkpoint: Checkpoint, seed: &Secret, interval: Duration, progress: &Progress, mut save: C) -> Result<KeyPair, Error> where C: FnMut(&Checkpoint) -> Result<(), Error> {
+		let prefix = checkpoint.prefix.clone();
+		let offsets = checkpoint.offsets.clone();
+		let mut checkpoint = checkpoint;
+		let secret = try!(run_resumable(seed, &offsets, move |address: &Address| address.starts_with(&prefix), checkpoint.iterations, interval, progress, |offsets| {
+			checkpoint.offsets = offsets.to_vec();
+			save(&checkpoint)
+		}));
+		KeyPair::from_secret(secret)
+	}
 }
 
 impl Generator for Prefix {
 	fn generate(self) -> Result<KeyPair, Error> {
-		for _ in 0..self.iterations {
-			let keypair = try!(Random.generate());
-			if keypair.address().starts_with(&self.prefix) {
-				return Ok(keypair)
-			}
-		}
-
-		Err(Error::Custom("Could not find keypair".into()))
+		let prefix = self.prefix;
+		Incremental::with_progress(move |address: &Address| address.starts_with(&prefix), self.iterations, self.threads, self.progress).generate()
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use {Generator, Prefix};
+	use std::time::Duration;
+	use {Generator, Random, Prefix, Progress, Checkpoint, SearchSeed};
 
 	#[test]
 	fn prefix_generator() {
@@ -38,4 +70,52 @@ mod tests {
 		let keypair = Prefix::new(prefix.clone(), usize::max_value()).generate().unwrap();
 		assert!(keypair.address().starts_with(&prefix));
 	}
+
+	#[test]
+	fn prefix_generator_with_threads() {
+		let prefix = vec![0xffu8];
+		let keypair = Prefix::with_threads(prefix.clone(), usize::max_value(), 4).generate().unwrap();
+		assert!(keypair.address().starts_with(&prefix));
+	}
+
+	#[test]
+	fn prefix_generator_iterations_cap() {
+		// 20 bytes prefix is never found, all workers together must stop after 64 attempts
+		let prefix = vec![0xffu8; 20];
+		assert!(Prefix::with_threads(prefix, 64, 4).generate().is_err());
+	}
+
+	#[test]
+	fn prefix_resume() {
+		let seed = Random.generate().unwrap().secret().clone();
+		let checkpoint = Checkpoint::new(vec![0xff], usize::max_value(), 2, SearchSeed::Plain(seed.clone()));
+		let keypair = Prefix::resume(checkpoint, &seed, Duration::from_secs(60), &Progress::new(), |_| Ok(())).unwrap();
+		assert_eq!(keypair.address()[0], 0xff);
+	}
+
+	#[test]
+	fn prefix_resume_continues() {
+		// 20 bytes prefix is never found, search stops at the iterations cap
+		let seed = Random.generate().unwrap().secret().clone();
+		let checkpoint = Checkpoint::new(vec![0xff; 20], 512, 2, SearchSeed::Plain(seed.clone()));
+		let mut saved = None;
+		assert!(Prefix::resume(checkpoint, &seed, Duration::from_secs(60), &Progress::new(), |checkpoint| {
+			saved = Some(checkpoint.clone());
+			Ok(())
+		}).is_err());
+
+		let mut checkpoint = saved.unwrap();
+		assert!(checkpoint.attempts() >= 512);
+		let offsets = checkpoint.offsets.clone();
+		checkpoint.iterations = 2048;
+		let mut saved = None;
+		assert!(Prefix::resume(checkpoint, &seed, Duration::from_secs(60), &Progress::new(), |checkpoint| {
+			saved = Some(checkpoint.clone());
+			Ok(())
+		}).is_err());
+
+		let checkpoint = saved.unwrap();
+		assert!(checkpoint.attempts() >= 2048);
+		assert!(checkpoint.offsets.iter().zip(offsets.iter()).all(|(new, old)| new >= old));
+	}
 }
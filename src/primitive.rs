@@ -2,10 +2,11 @@ use std::ops::{Deref, DerefMut};
 use std::{fmt, cmp, hash};
 use std::str::FromStr;
 use rustc_serialize::hex::{ToHex, FromHex};
+use keccak::Keccak256;
 use Error;
 
 macro_rules! impl_primitive {
-	($name: ident, $size: expr, $err: expr) => {
+	($name: ident, $size: expr) => {
 
 		#[repr(C)]
 		#[derive(Eq)]
@@ -23,21 +24,6 @@ macro_rules! impl_primitive {
 			}
 		}
 
-		impl FromStr for $name {
-			type Err = Error;
-
-			fn from_str(s: &str) -> Result<Self, Self::Err> {
-				match s.from_hex() {
-					Ok(ref hex) if hex.len() == $size => {
-						let mut res = $name::default();
-						res.copy_from_slice(hex);
-						Ok(res)
-					},
-					_ => Err($err)
-				}
-			}
-		}
-
 		impl PartialEq for $name {
 			fn eq(&self, other: &Self) -> bool {
 				let self_ref: &[u8] = &self.0;
@@ -111,12 +97,111 @@ macro_rules! impl_primitive {
 	}
 }
 
-impl_primitive!(Address, 20, Error::InvalidAddress);
-impl_primitive!(Secret, 32, Error::InvalidSecret);
-impl_primitive!(Message, 32, Error::InvalidMessage);
-impl_primitive!(Public, 64, Error::InvalidPublic);
+macro_rules! impl_from_str {
+	($name: ident, $size: expr, $err: expr) => {
+		impl FromStr for $name {
+			type Err = Error;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				match s.from_hex() {
+					Ok(ref hex) if hex.len() == $size => {
+						let mut res = $name::default();
+						res.copy_from_slice(hex);
+						Ok(res)
+					},
+					_ => Err($err)
+				}
+			}
+		}
+	}
+}
+
+impl_primitive!(Address, 20);
+impl_primitive!(Secret, 32);
+impl_primitive!(Message, 32);
+impl_primitive!(Public, 64);
+
+impl_from_str!(Secret, 32, Error::InvalidSecret);
+impl_from_str!(Message, 32, Error::InvalidMessage);
+impl_from_str!(Public, 64, Error::InvalidPublic);
+
+/// Applies checksum casing of `hash` to lowercase hex address.
+fn checksum(hex: &str, hash: &[u8; 32]) -> String {
+	hex.chars().enumerate().map(|(i, c)| {
+		let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+		if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+	}).collect()
+}
+
+impl Address {
+	/// Returns EIP-55 mixed-case checksum encoding of the address.
+	pub fn to_checksum(&self) -> String {
+		let hex = self.to_hex();
+		checksum(&hex, &hex.as_bytes().keccak256())
+	}
+
+	/// Returns EIP-1191 chain-aware checksum encoding of the address.
+	pub fn to_chain_checksum(&self, chain_id: u64) -> String {
+		let hex = self.to_hex();
+		checksum(&hex, &format!("{}0x{}", chain_id, hex).as_bytes().keccak256())
+	}
+}
+
+impl FromStr for Address {
+	type Err = Error;
+
+	/// Parses address, optionally `0x` prefixed. Mixed-case input must have valid EIP-55 checksum.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = if s.starts_with("0x") { &s[2..] } else { s };
+		let hex = match s.from_hex() {
+			Ok(ref hex) if hex.len() == 20 => hex.clone(),
+			_ => return Err(Error::InvalidAddress),
+		};
+
+		let mut address = Address::default();
+		address.copy_from_slice(&hex);
+
+		let mixed_case = s.chars().any(|c| c.is_lowercase()) && s.chars().any(|c| c.is_uppercase());
+		if mixed_case && address.to_checksum() != s {
+			return Err(Error::InvalidAddress);
+		}
+
+		Ok(address)
+	}
+}
 
 #[cfg(test)]
 mod tests {
+	use std::str::FromStr;
+	use Address;
+
+	#[test]
+	fn address_checksum() {
+		let addresses = [
+			"5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+			"fB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+			"dbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+			"D1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+		];
+
+		for address in &addresses {
+			assert_eq!(&Address::from_str(&address.to_lowercase()).unwrap().to_checksum(), address);
+		}
+	}
 
+	#[test]
+	fn address_chain_checksum() {
+		let address = Address::from_str("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+		assert_eq!(address.to_chain_checksum(30), "5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD");
+		assert_eq!(address.to_chain_checksum(31), "5aAeb6053F3e94c9b9A09F33669435E7EF1BEaEd");
+	}
+
+	#[test]
+	fn address_from_str() {
+		let expected = Address::from_str("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+		assert_eq!(Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap(), expected);
+		assert_eq!(Address::from_str("5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED").unwrap(), expected);
+		assert!(Address::from_str("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_err());
+		assert!(Address::from_str("5aaeb6053f3e94c9b9a09f33669435e7ef1bea").is_err());
+	}
 }
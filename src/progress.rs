@@ -0,0 +1,147 @@
+//! Progress reporting and cancellation of long-running searches.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+fn seconds(duration: Duration) -> f64 {
+	duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
+
+/// Search progress shared by the searching generator and its caller. Clones share the same state.
+///
+/// Searchers take it in their `with_progress` constructors. They count each tried candidate and stop with
+/// `Error::Cancelled` once it's cancelled, unless documented otherwise. Their `iterations` limit, if any,
+/// is shared by all worker threads.
+#[derive(Debug, Clone)]
+pub struct Progress {
+	attempts: Arc<AtomicUsize>,
+	cancelled: Arc<AtomicBool>,
+	started: Instant,
+	deadline: Option<Instant>,
+}
+
+impl Default for Progress {
+	fn default() -> Self {
+		Progress::new()
+	}
+}
+
+impl Progress {
+	pub fn new() -> Self {
+		Progress {
+			attempts: Arc::new(AtomicUsize::new(0)),
+			cancelled: Arc::new(AtomicBool::new(false)),
+			started: Instant::now(),
+			deadline: None,
+		}
+	}
+
+	/// Search is cancelled once `timeout` elapses.
+	pub fn with_timeout(timeout: Duration) -> Self {
+		let mut progress = Progress::new();
+		progress.deadline = Some(progress.started + timeout);
+		progress
+	}
+
+	/// Stops the search. Generator returns an error.
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	/// Returns true if the search was cancelled or its deadline passed.
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed) || self.deadline.map_or(false, |deadline| Instant::now() >= deadline)
+	}
+
+	/// Number of attempts so far.
+	pub fn attempts(&self) -> usize {
+		self.attempts.load(Ordering::Relaxed)
+	}
+
+	/// Counter updated by the search workers.
+	pub fn counter(&self) -> &AtomicUsize {
+		&self.attempts
+	}
+
+	pub fn elapsed(&self) -> Duration {
+		self.started.elapsed()
+	}
+
+	/// Attempts per second.
+	pub fn rate(&self) -> f64 {
+		let elapsed = seconds(self.elapsed());
+		match elapsed > 0.0 {
+			true => self.attempts() as f64 / elapsed,
+			false => 0.0,
+		}
+	}
+
+	/// Probability, that search expecting `expected` attempts would have already succeeded.
+	pub fn probability(&self, expected: f64) -> f64 {
+		1.0 - (-(self.attempts() as f64) / expected).exp()
+	}
+
+	/// Expected time to find the match at the current rate. Each attempt is independent,
+	/// so it doesn't decrease with attempts already made.
+	pub fn eta(&self, expected: f64) -> Option<Duration> {
+		self.time_of(expected)
+	}
+
+	/// Fraction of exhaustive search over `size` candidates done so far.
+	pub fn searched(&self, size: f64) -> f64 {
+		(self.attempts() as f64 / size).min(1.0)
+	}
+
+	/// Time to try the rest of `size` candidates at the current rate.
+	pub fn remaining(&self, size: f64) -> Option<Duration> {
+		self.time_of((size - self.attempts() as f64).max(0.0))
+	}
+
+	fn time_of(&self, attempts: f64) -> Option<Duration> {
+		let rate = self.rate();
+		if rate <= 0.0 || !(attempts / rate).is_finite() || attempts / rate > u64::max_value() as f64 {
+			return None;
+		}
+		let seconds = attempts / rate;
+		Some(Duration::new(seconds as u64, (seconds.fract() * 1e9) as u32))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::Ordering;
+	use std::thread;
+	use std::time::Duration;
+	use super::Progress;
+
+	#[test]
+	fn progress_counts_and_cancels() {
+		let progress = Progress::new();
+		let shared = progress.clone();
+		shared.counter().fetch_add(1000, Ordering::Relaxed);
+		assert_eq!(progress.attempts(), 1000);
+		assert!(progress.probability(1000.0) > 0.63 && progress.probability(1000.0) < 0.64);
+
+		thread::sleep(Duration::from_millis(10));
+		assert!(progress.rate() > 0.0);
+		assert!(progress.eta(1000.0).unwrap() < Duration::from_secs(1));
+		assert_eq!(progress.searched(4000.0), 0.25);
+		assert_eq!(progress.searched(500.0), 1.0);
+		assert!(progress.remaining(4000.0).unwrap() > progress.eta(1000.0).unwrap());
+		assert_eq!(progress.remaining(500.0), Some(Duration::new(0, 0)));
+
+		assert!(!progress.is_cancelled());
+		shared.cancel();
+		assert!(progress.is_cancelled());
+	}
+
+	#[test]
+	fn progress_deadline() {
+		let progress = Progress::with_timeout(Duration::from_millis(10));
+		assert!(!progress.is_cancelled());
+		thread::sleep(Duration::from_millis(20));
+		assert!(progress.is_cancelled());
+		assert_eq!(progress.eta(1000.0), None);
+	}
+}
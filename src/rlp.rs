@@ -0,0 +1,266 @@
+//! Recursive Length Prefix encoding.
+
+use Error;
+
+/// Returns big endian bytes of the value without leading zeros.
+fn minimal_bytes(value: u128) -> Vec<u8> {
+	let bytes = value.to_be_bytes();
+	let zeros = bytes.iter().take_while(|b| **b == 0).count();
+	bytes[zeros..].to_vec()
+}
+
+fn append_header(out: &mut Vec<u8>, len: usize, short_offset: u8, long_offset: u8) {
+	if len <= 55 {
+		out.push(short_offset + len as u8);
+	} else {
+		let len_bytes = minimal_bytes(len as u128);
+		out.push(long_offset + len_bytes.len() as u8);
+		out.extend_from_slice(&len_bytes);
+	}
+}
+
+/// Appends items of RLP list.
+#[derive(Debug, Default, Clone)]
+pub struct RlpStream {
+	payload: Vec<u8>,
+}
+
+impl RlpStream {
+	/// Creates new empty list.
+	pub fn new() -> Self {
+		RlpStream::default()
+	}
+
+	/// Appends byte string.
+	pub fn append_bytes(&mut self, data: &[u8]) -> &mut Self {
+		if data.len() == 1 && data[0] < 0x80 {
+			self.payload.push(data[0]);
+		} else {
+			append_header(&mut self.payload, data.len(), 0x80, 0xb7);
+			self.payload.extend_from_slice(data);
+		}
+		self
+	}
+
+	/// Appends unsigned integer as big endian byte string without leading zeros.
+	pub fn append_uint(&mut self, value: u128) -> &mut Self {
+		self.append_bytes(&minimal_bytes(value))
+	}
+
+	/// Appends big endian unsigned integer of any size, eg. signature `r` and `s`, without leading zeros.
+	pub fn append_scalar(&mut self, value: &[u8]) -> &mut Self {
+		let zeros = value.iter().take_while(|b| **b == 0).count();
+		self.append_bytes(&value[zeros..])
+	}
+
+	/// Appends nested list.
+	pub fn append_list(&mut self, list: &RlpStream) -> &mut Self {
+		self.append_raw(&list.out())
+	}
+
+	/// Appends already encoded item.
+	pub fn append_raw(&mut self, rlp: &[u8]) -> &mut Self {
+		self.payload.extend_from_slice(rlp);
+		self
+	}
+
+	/// Returns encoded list.
+	pub fn out(&self) -> Vec<u8> {
+		let mut out = Vec::with_capacity(self.payload.len() + 9);
+		append_header(&mut out, self.payload.len(), 0xc0, 0xf7);
+		out.extend_from_slice(&self.payload);
+		out
+	}
+}
+
+/// Decoded RLP item, a byte string or a list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rlp<'a> {
+	is_list: bool,
+	payload: &'a [u8],
+}
+
+/// Decodes header of the first item, returns list flag, payload offset and payload length.
+fn decode_header(data: &[u8]) -> Result<(bool, usize, usize), Error> {
+	let first = match data.first() {
+		Some(first) => *first,
+		None => return Err(Error::InvalidRlp),
+	};
+
+	let (is_list, offset, len) = match first {
+		0x00..=0x7f => (false, 0, 1),
+		0x80..=0xb7 => (false, 1, (first - 0x80) as usize),
+		0xb8..=0xbf => {
+			let (offset, len) = try!(decode_long_length(data, (first - 0xb7) as usize));
+			(false, offset, len)
+		},
+		0xc0..=0xf7 => (true, 1, (first - 0xc0) as usize),
+		_ => {
+			let (offset, len) = try!(decode_long_length(data, (first - 0xf7) as usize));
+			(true, offset, len)
+		},
+	};
+
+	if data.len() - offset < len {
+		return Err(Error::InvalidRlp);
+	}
+
+	// single byte below 0x80 must be encoded as itself
+	if !is_list && offset == 1 && len == 1 && data[1] < 0x80 {
+		return Err(Error::InvalidRlp);
+	}
+
+	Ok((is_list, offset, len))
+}
+
+fn decode_long_length(data: &[u8], len_of_len: usize) -> Result<(usize, usize), Error> {
+	if data.len() <= len_of_len || len_of_len > 8 || data[1] == 0 {
+		return Err(Error::InvalidRlp);
+	}
+
+	let len = data[1..len_of_len + 1].iter().fold(0u64, |len, b| (len << 8) | *b as u64);
+	if len <= 55 || len > data.len() as u64 {
+		return Err(Error::InvalidRlp);
+	}
+	Ok((len_of_len + 1, len as usize))
+}
+
+impl<'a> Rlp<'a> {
+	/// Decodes single item. Fails if data contains anything after the item.
+	pub fn new(data: &'a [u8]) -> Result<Self, Error> {
+		let (is_list, offset, len) = try!(decode_header(data));
+		if offset + len != data.len() {
+			return Err(Error::InvalidRlp);
+		}
+
+		Ok(Rlp {
+			is_list: is_list,
+			payload: &data[offset..],
+		})
+	}
+
+	/// Returns true if item is a list.
+	pub fn is_list(&self) -> bool {
+		self.is_list
+	}
+
+	/// Returns content of byte string.
+	pub fn data(&self) -> Result<&'a [u8], Error> {
+		match self.is_list {
+			true => Err(Error::InvalidRlp),
+			false => Ok(self.payload),
+		}
+	}
+
+	/// Returns items of the list.
+	pub fn items(&self) -> Result<Vec<Rlp<'a>>, Error> {
+		if !self.is_list {
+			return Err(Error::InvalidRlp);
+		}
+
+		let mut items = Vec::new();
+		let mut rest = self.payload;
+		while !rest.is_empty() {
+			let (_, offset, len) = try!(decode_header(rest));
+			items.push(try!(Rlp::new(&rest[..offset + len])));
+			rest = &rest[offset + len..];
+		}
+		Ok(items)
+	}
+
+	/// Returns content of byte string decoded as big endian unsigned integer without leading zeros.
+	pub fn as_scalar(&self) -> Result<&'a [u8], Error> {
+		let data = try!(self.data());
+		match data.first() {
+			Some(&0) => Err(Error::InvalidRlp),
+			_ => Ok(data),
+		}
+	}
+
+	/// Returns content of byte string decoded as unsigned integer.
+	pub fn as_uint(&self) -> Result<u128, Error> {
+		let data = try!(self.as_scalar());
+		if data.len() > 16 {
+			return Err(Error::InvalidRlp);
+		}
+		Ok(data.iter().fold(0u128, |value, b| (value << 8) | *b as u128))
+	}
+
+	/// Returns content of byte string decoded as unsigned 64 bits integer.
+	pub fn as_u64(&self) -> Result<u64, Error> {
+		match try!(self.as_uint()) {
+			value if value > ::std::u64::MAX as u128 => Err(Error::InvalidRlp),
+			value => Ok(value as u64),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use super::{RlpStream, Rlp};
+
+	#[test]
+	fn encode_strings() {
+		assert_eq!(RlpStream::new().append_bytes(b"dog").append_bytes(b"").append_bytes(&[0x7f]).append_bytes(&[0x80]).out().to_hex(), "c883646f67807f8180");
+		let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+		let expected = format!("f83ab838{}", lorem.to_hex());
+		assert_eq!(RlpStream::new().append_bytes(lorem).out().to_hex(), expected);
+	}
+
+	#[test]
+	fn encode_integers() {
+		assert_eq!(RlpStream::new().append_uint(0).append_uint(15).append_uint(1024).out().to_hex(), "c5800f820400");
+		assert_eq!(RlpStream::new().append_uint(::std::u128::MAX).out().to_hex(), "d190ffffffffffffffffffffffffffffffff");
+		assert_eq!(RlpStream::new().append_scalar(&[0, 0, 4, 0]).append_scalar(&[0, 0]).out().to_hex(), "c482040080");
+	}
+
+	#[test]
+	fn encode_nested_lists() {
+		// set theoretical representation of three
+		let empty = RlpStream::new();
+		let mut one = RlpStream::new();
+		one.append_list(&empty);
+		let mut two = RlpStream::new();
+		two.append_list(&empty).append_list(&one);
+		let mut three = RlpStream::new();
+		three.append_list(&empty).append_list(&one).append_list(&two);
+		assert_eq!(three.out().to_hex(), "c7c0c1c0c3c0c1c0");
+	}
+
+	#[test]
+	fn decode_roundtrip() {
+		let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+		let mut nested = RlpStream::new();
+		nested.append_uint(1024).append_bytes(lorem);
+		let mut stream = RlpStream::new();
+		stream.append_bytes(b"dog").append_uint(0).append_list(&nested);
+		let encoded = stream.out();
+
+		let rlp = Rlp::new(&encoded).unwrap();
+		assert!(rlp.is_list());
+		let items = rlp.items().unwrap();
+		assert_eq!(items.len(), 3);
+		assert_eq!(items[0].data().unwrap(), b"dog");
+		assert_eq!(items[1].as_uint().unwrap(), 0);
+		let nested = items[2].items().unwrap();
+		assert_eq!(nested[0].as_u64().unwrap(), 1024);
+		assert_eq!(nested[1].data().unwrap(), &lorem[..]);
+	}
+
+	#[test]
+	fn decode_invalid() {
+		// trailing bytes
+		assert!(Rlp::new(&"c08080".from_hex().unwrap()).is_err());
+		// truncated payload
+		assert!(Rlp::new(&"83646f".from_hex().unwrap()).is_err());
+		// single byte encoded as string
+		assert!(Rlp::new(&"817f".from_hex().unwrap()).is_err());
+		// short string with long header
+		assert!(Rlp::new(&"b803646f67".from_hex().unwrap()).is_err());
+		// integer with leading zero
+		assert!(Rlp::new(&"820001".from_hex().unwrap()).unwrap().as_uint().is_err());
+		assert!(Rlp::new(&"".from_hex().unwrap()).is_err());
+		assert!(Rlp::new(&"c0".from_hex().unwrap()).unwrap().data().is_err());
+	}
+}
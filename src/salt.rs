@@ -0,0 +1,161 @@
+//! CREATE2 salt search. Salts are consecutive values from random start, so that
+//! the only work per attempt is single keccak256 of the CREATE2 preimage.
+
+use std::cmp;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use rand::Rng;
+use rand::os::OsRng;
+use keccak::Keccak256;
+use incremental::run_workers;
+use super::{Matcher, Progress, Address, Error};
+
+/// Number of salts claimed by worker at once.
+const CHUNK: usize = 1024;
+
+/// Expected CREATE2 address.
+#[derive(Debug, Clone)]
+pub enum SaltTarget {
+	/// Address matches the pattern.
+	Pattern(Matcher),
+	/// Address starts with at least given number of zero bytes.
+	ZeroBytes(usize),
+}
+
+impl SaltTarget {
+	/// Returns true if the address is the expected one.
+	pub fn is_match(&self, address: &Address) -> bool {
+		match *self {
+			SaltTarget::Pattern(ref matcher) => matcher.is_match(address),
+			SaltTarget::ZeroBytes(bytes) => address.iter().take_while(|b| **b == 0).count() >= bytes,
+		}
+	}
+
+	/// Returns expected number of attempts to find matching address, `None` if unknown.
+	pub fn difficulty(&self) -> Option<f64> {
+		match *self {
+			SaltTarget::Pattern(ref matcher) => matcher.difficulty(),
+			SaltTarget::ZeroBytes(bytes) => Some(256f64.powi(bytes as i32)),
+		}
+	}
+}
+
+/// Searches for CREATE2 salt giving contract address matching the target, on multiple threads.
+pub struct SaltMiner {
+	deployer: Address,
+	init_code_hash: [u8; 32],
+	target: SaltTarget,
+	iterations: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl SaltMiner {
+	pub fn new(deployer: Address, init_code_hash: [u8; 32], target: SaltTarget, iterations: usize, threads: usize) -> Self {
+		SaltMiner::with_progress(deployer, init_code_hash, target, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(deployer: Address, init_code_hash: [u8; 32], target: SaltTarget, iterations: usize, threads: usize, progress: Progress) -> Self {
+		SaltMiner {
+			deployer: deployer,
+			init_code_hash: init_code_hash,
+			target: target,
+			iterations: iterations,
+			threads: cmp::max(threads, 1),
+			progress: progress,
+		}
+	}
+
+	/// Returns the salt and the contract address.
+	pub fn mine(self) -> Result<([u8; 32], Address), Error> {
+		let mut preimage = [0u8; 85];
+		preimage[0] = 0xff;
+		preimage[1..21].copy_from_slice(&self.deployer[..]);
+		preimage[53..85].copy_from_slice(&self.init_code_hash);
+
+		let (target, iterations) = (self.target, self.iterations);
+		let attempts = AtomicUsize::new(0);
+		let progress = self.progress.clone();
+		let found = try!(run_workers(self.threads, &self.progress, move |done| search(preimage, &target, iterations, done, &attempts, &progress)));
+		found.ok_or_else(|| Error::Custom("Could not find salt".into()))
+	}
+}
+
+/// Tries consecutive salts from random start until target matches, `done` is set, `progress` is cancelled or `attempts` reach `iterations`.
+fn search(mut preimage: [u8; 85], target: &SaltTarget, iterations: usize, done: &AtomicBool, attempts: &AtomicUsize, progress: &Progress) -> Option<Result<([u8; 32], Address), Error>> {
+	match OsRng::new() {
+		Ok(mut rng) => rng.fill_bytes(&mut preimage[21..53]),
+		Err(err) => return Some(Err(err.into())),
+	}
+
+	let mut address = Address::default();
+	while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+		let claimed = attempts.fetch_add(CHUNK, Ordering::Relaxed);
+		if claimed >= iterations {
+			return None;
+		}
+
+		let count = cmp::min(CHUNK, iterations - claimed);
+		progress.counter().fetch_add(count, Ordering::Relaxed);
+		for _ in 0..count {
+			// increment salt as big endian number, wrapping around
+			for byte in preimage[21..53].iter_mut().rev() {
+				*byte = byte.wrapping_add(1);
+				if *byte != 0 {
+					break;
+				}
+			}
+
+			let hash = preimage.keccak256();
+			address.copy_from_slice(&hash[12..]);
+			if target.is_match(&address) {
+				let mut salt = [0u8; 32];
+				salt.copy_from_slice(&preimage[21..53]);
+				return Some(Ok((salt, address)));
+			}
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use std::time::Duration;
+	use {Matcher, Progress, Address, Error, create2_address};
+	use super::{SaltMiner, SaltTarget};
+
+	#[test]
+	fn mine_pattern() {
+		let deployer = Address::from_str("00000000000000000000000000000000deadbeef").unwrap();
+		let matcher = Matcher::from_str("abc").unwrap();
+		let (salt, address) = SaltMiner::new(deployer.clone(), [1u8; 32], SaltTarget::Pattern(matcher.clone()), usize::max_value(), 2).mine().unwrap();
+		assert!(matcher.is_match(&address));
+		assert_eq!(create2_address(&deployer, &salt, &[1u8; 32]), address);
+	}
+
+	#[test]
+	fn mine_zero_bytes() {
+		let deployer = Address::from_str("00000000000000000000000000000000deadbeef").unwrap();
+		let (salt, address) = SaltMiner::new(deployer.clone(), [2u8; 32], SaltTarget::ZeroBytes(1), usize::max_value(), 2).mine().unwrap();
+		assert_eq!(address[0], 0);
+		assert_eq!(create2_address(&deployer, &salt, &[2u8; 32]), address);
+	}
+
+	#[test]
+	fn mine_iterations_cap() {
+		let progress = Progress::new();
+		assert!(SaltMiner::with_progress(Address::default(), [0u8; 32], SaltTarget::ZeroBytes(20), 5000, 2, progress.clone()).mine().is_err());
+		assert_eq!(progress.attempts(), 5000);
+	}
+
+	#[test]
+	fn mine_cancelled() {
+		let progress = Progress::with_timeout(Duration::from_millis(100));
+		match SaltMiner::with_progress(Address::default(), [0u8; 32], SaltTarget::ZeroBytes(20), usize::max_value(), 2, progress).mine() {
+			Err(Error::Cancelled) => (),
+			other => panic!("expected cancelled search, got {:?}", other),
+		}
+		assert_eq!(SaltTarget::ZeroBytes(2).difficulty(), Some(65536.0));
+	}
+}
@@ -0,0 +1,227 @@
+//! Search for addresses with as many zero bytes as possible.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::{cmp, thread};
+use super::{Random, Walk, Generator, Progress, KeyPair, Secret, Address, Error, public_to_address};
+
+/// Address ranking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Score {
+	/// Number of leading zero bytes.
+	LeadingZeroBytes,
+	/// Number of zero bytes anywhere in the address.
+	ZeroBytes,
+}
+
+impl FromStr for Score {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"leading" => Ok(Score::LeadingZeroBytes),
+			"total" => Ok(Score::ZeroBytes),
+			_ => Err(Error::InvalidScore(s.into())),
+		}
+	}
+}
+
+impl Score {
+	pub fn score(&self, address: &Address) -> usize {
+		match *self {
+			Score::LeadingZeroBytes => address.iter().take_while(|b| **b == 0).count(),
+			Score::ZeroBytes => address.iter().filter(|b| **b == 0).count(),
+		}
+	}
+
+	/// Returns expected number of random addresses to try to find one scoring at least `target`.
+	pub fn difficulty(&self, target: usize) -> f64 {
+		let len = Address::default().len();
+		match *self {
+			Score::LeadingZeroBytes => 256f64.powi(target as i32),
+			Score::ZeroBytes => {
+				// probability of at least `target` zero bytes out of `len`
+				let (zero, other) = (1.0 / 256.0, 255.0 / 256.0);
+				let mut binomial = 1.0;
+				let mut probability = 0.0;
+				for i in 0..len + 1 {
+					if i >= target {
+						probability += binomial * f64::powi(zero, i as i32) * f64::powi(other, (len - i) as i32);
+					}
+					binomial = binomial * (len - i) as f64 / (i + 1) as f64;
+				}
+				1.0 / probability
+			},
+		}
+	}
+}
+
+/// Searches for keypair with the best scoring address, until score reaches the target or search is cancelled.
+pub struct Scoring {
+	score: Score,
+	target: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl Scoring {
+	/// Searches until address scores at least `target`.
+	pub fn new(score: Score, target: usize, threads: usize) -> Self {
+		Scoring::with_progress(score, target, threads, Progress::new())
+	}
+
+	/// Once `progress` is cancelled, eg. with `Progress::with_timeout`, returns the best keypair found so far.
+	pub fn with_progress(score: Score, target: usize, threads: usize, progress: Progress) -> Self {
+		Scoring {
+			score: score,
+			target: target,
+			threads: cmp::max(threads, 1),
+			progress: progress,
+		}
+	}
+
+	/// Returns expected number of attempts to reach the target.
+	pub fn difficulty(&self) -> f64 {
+		self.score.difficulty(self.target)
+	}
+
+	/// Calls `best` with each keypair scoring better than all previous ones.
+	/// Returns the best keypair and its score, `None` if the search was cancelled before the first one was found.
+	pub fn search<F>(self, mut best: F) -> Result<Option<(KeyPair, usize)>, Error> where F: FnMut(&KeyPair, usize) {
+		let score = self.score;
+		let done = Arc::new(AtomicBool::new(false));
+		let best_score = Arc::new(AtomicUsize::new(0));
+		let (tx, rx) = mpsc::channel();
+
+		let workers = (0..self.threads).map(|_| {
+			let (done, best_score, progress, tx) = (done.clone(), best_score.clone(), self.progress.clone(), tx.clone());
+			thread::spawn(move || search(score, &done, &best_score, &progress, &tx))
+		}).collect::<Vec<_>>();
+
+		// workers stop on cancellation and drop their senders
+		drop(tx);
+		let mut result = None;
+		let error = loop {
+			let (secret, score) = match rx.recv() {
+				Ok(Ok(found)) => found,
+				Ok(Err(err)) => break Some(err),
+				Err(_) => break None,
+			};
+
+			// scores sent by different workers may arrive out of order
+			if result.as_ref().map_or(false, |&(_, best)| score <= best) {
+				continue;
+			}
+
+			let keypair = match KeyPair::from_secret(secret) {
+				Ok(keypair) => keypair,
+				Err(err) => break Some(err),
+			};
+			best(&keypair, score);
+			result = Some((keypair, score));
+			if score >= self.target {
+				break None;
+			}
+		};
+
+		done.store(true, Ordering::Relaxed);
+		for worker in workers {
+			let _ = worker.join();
+		}
+
+		match error {
+			Some(err) => Err(err),
+			None => Ok(result),
+		}
+	}
+}
+
+/// Walks from random secrets, sending secrets of addresses scoring better than `best_score`, until `done` is set
+/// or `progress` is cancelled.
+fn search(score: Score, done: &AtomicBool, best_score: &AtomicUsize, progress: &Progress, tx: &Sender<Result<(Secret, usize), Error>>) {
+	let mut publics = Vec::new();
+	'restart: while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+		let mut walk = match Random.generate() {
+			Ok(keypair) => Walk::new(&keypair),
+			Err(err) => {
+				let _ = tx.send(Err(err));
+				return;
+			},
+		};
+
+		while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+			let start = walk.offset();
+			if walk.next_batch(usize::max_value(), &mut publics).is_err() {
+				continue 'restart;
+			}
+			progress.counter().fetch_add(publics.len(), Ordering::Relaxed);
+
+			for (i, public) in publics.iter().enumerate() {
+				let value = score.score(&public_to_address(public));
+				// raise the best score atomically, so that equal and lower scores are not sent again
+				if best_score.fetch_max(value, Ordering::Relaxed) < value {
+					let _ = tx.send(walk.secret(start + i as u64 + 1).map(|secret| (secret, value)));
+				}
+			}
+		}
+	}
+}
+
+impl Generator for Scoring {
+	fn generate(self) -> Result<KeyPair, Error> {
+		let progress = self.progress.clone();
+		match try!(self.search(|_, _| ())) {
+			Some((keypair, _)) => Ok(keypair),
+			None if progress.is_cancelled() => Err(Error::Cancelled),
+			None => Err(Error::Custom("Could not find keypair".into())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use std::time::Duration;
+	use {Generator, Progress, Address, Error};
+	use super::{Score, Scoring};
+
+	#[test]
+	fn scores() {
+		let address = Address::from_str("0000ab00cd0000000000000000000000000000ef").unwrap();
+		assert_eq!(Score::LeadingZeroBytes.score(&address), 2);
+		assert_eq!(Score::ZeroBytes.score(&address), 17);
+		assert_eq!(Score::from_str("total").unwrap(), Score::ZeroBytes);
+		assert_eq!(Score::LeadingZeroBytes.difficulty(2), 65536.0);
+		assert_eq!(Score::ZeroBytes.difficulty(0), 1.0);
+		// 1 / (1 - (255 / 256)^20)
+		assert!((Score::ZeroBytes.difficulty(1) - 13.3).abs() < 0.1);
+		match Score::from_str("zeros") {
+			Err(Error::InvalidScore(ref s)) if s == "zeros" => (),
+			_ => panic!("expected invalid score error"),
+		}
+	}
+
+	#[test]
+	fn scoring_reaches_target() {
+		let mut scores = Vec::new();
+		let (keypair, score) = Scoring::new(Score::LeadingZeroBytes, 1, 2).search(|keypair, score| {
+			assert_eq!(Score::LeadingZeroBytes.score(&keypair.address()), score);
+			scores.push(score);
+		}).unwrap().unwrap();
+
+		assert!(score >= 1);
+		assert_eq!(Score::LeadingZeroBytes.score(&keypair.address()), score);
+		assert_eq!(scores.last(), Some(&score));
+		assert!(scores.windows(2).all(|w| w[0] < w[1]));
+	}
+
+	#[test]
+	fn scoring_time_limit() {
+		let progress = Progress::with_timeout(Duration::from_secs(1));
+		let keypair = Scoring::with_progress(Score::ZeroBytes, 20, 1, progress.clone()).generate().unwrap();
+		assert!(Score::ZeroBytes.score(&keypair.address()) >= 1);
+		assert!(progress.attempts() > 0);
+	}
+}
@@ -0,0 +1,144 @@
+//! Scrypt key derivation function (RFC 7914).
+//!
+//! rust-crypto requires `n < 2^(128 * r / 8)`, which rejects parameters
+//! used by real key files (eg. `n = 262144, r = 1, p = 8`).
+
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha256;
+use super::Error;
+
+/// Maximum memory used by scrypt, parameters come from untrusted key files.
+const MAX_MEMORY: u64 = 2 << 30;
+
+fn salsa20_8(block: &mut [u32; 16]) {
+	let mut x = *block;
+	for _ in 0..4 {
+		macro_rules! quarter {
+			($a: expr, $b: expr, $c: expr, $d: expr) => {
+				x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+				x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+				x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+				x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+			}
+		}
+		// columns
+		quarter!(0, 4, 8, 12);
+		quarter!(5, 9, 13, 1);
+		quarter!(10, 14, 2, 6);
+		quarter!(15, 3, 7, 11);
+		// rows
+		quarter!(0, 1, 2, 3);
+		quarter!(5, 6, 7, 4);
+		quarter!(10, 11, 8, 9);
+		quarter!(15, 12, 13, 14);
+	}
+	for i in 0..16 {
+		block[i] = block[i].wrapping_add(x[i]);
+	}
+}
+
+/// Mixes `input` of `2 * r` 64 bytes blocks into `output`.
+fn block_mix(input: &[u32], output: &mut [u32], r: usize) {
+	let mut x = [0u32; 16];
+	x.copy_from_slice(&input[(2 * r - 1) * 16..]);
+	for i in 0..2 * r {
+		for j in 0..16 {
+			x[j] ^= input[i * 16 + j];
+		}
+		salsa20_8(&mut x);
+		// even blocks go to the first half, odd blocks to the second one
+		let position = (i / 2 + (i % 2) * r) * 16;
+		output[position..position + 16].copy_from_slice(&x);
+	}
+}
+
+fn ro_mix(block: &mut [u32], n: usize, r: usize) {
+	let len = 32 * r;
+	let mut v = vec![0u32; n * len];
+	let mut x = block.to_vec();
+	let mut t = vec![0u32; len];
+
+	for i in 0..n {
+		v[i * len..(i + 1) * len].copy_from_slice(&x);
+		block_mix(&x, &mut t, r);
+		x.copy_from_slice(&t);
+	}
+
+	for _ in 0..n {
+		let j = x[(2 * r - 1) * 16] as usize & (n - 1);
+		for k in 0..len {
+			x[k] ^= v[j * len + k];
+		}
+		block_mix(&x, &mut t, r);
+		x.copy_from_slice(&t);
+	}
+
+	block.copy_from_slice(&x);
+}
+
+/// Derives `output.len()` bytes from the password and salt.
+/// `n` must be a power of 2 greater than 1, `r` and `p` must be positive.
+/// Fails if `128 * r * n` or `128 * r * p` bytes exceed 2 GiB.
+pub fn scrypt(password: &[u8], salt: &[u8], n: u32, r: u32, p: u32, output: &mut [u8]) -> Result<(), Error> {
+	if n < 2 || !n.is_power_of_two() || r == 0 || p == 0 || (r as u64) * (p as u64) >= 1 << 30 {
+		return Err(Error::Custom("Invalid scrypt parameters".into()));
+	}
+
+	let memory = |count: u32| (r as u64).checked_mul(128).and_then(|size| size.checked_mul(count as u64));
+	match (memory(n), memory(p)) {
+		(Some(v), Some(b)) if v <= MAX_MEMORY && b <= MAX_MEMORY => (),
+		_ => return Err(Error::Custom("Scrypt parameters exceed memory limit".into())),
+	}
+
+	let (n, r, p) = (n as usize, r as usize, p as usize);
+	let block_len = 128 * r;
+
+	let mut b = vec![0u8; p * block_len];
+	let mut mac = Hmac::new(Sha256::new(), password);
+	pbkdf2(&mut mac, salt, 1, &mut b);
+
+	let mut block = vec![0u32; 32 * r];
+	for chunk in b.chunks_mut(block_len) {
+		for (word, bytes) in block.iter_mut().zip(chunk.chunks(4)) {
+			*word = bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24;
+		}
+		ro_mix(&mut block, n, r);
+		for (word, bytes) in block.iter().zip(chunk.chunks_mut(4)) {
+			bytes[0] = *word as u8;
+			bytes[1] = (*word >> 8) as u8;
+			bytes[2] = (*word >> 16) as u8;
+			bytes[3] = (*word >> 24) as u8;
+		}
+	}
+
+	let mut mac = Hmac::new(Sha256::new(), password);
+	pbkdf2(&mut mac, &b, 1, output);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::ToHex;
+	use super::scrypt;
+
+	#[test]
+	fn rfc7914_test_vectors() {
+		let mut output = [0u8; 64];
+		scrypt(b"", b"", 16, 1, 1, &mut output).unwrap();
+		assert_eq!(output.to_hex(), "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906");
+
+		scrypt(b"password", b"NaCl", 1024, 8, 16, &mut output).unwrap();
+		assert_eq!(output.to_hex(), "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640");
+	}
+
+	#[test]
+	fn invalid_params() {
+		let mut output = [0u8; 32];
+		assert!(scrypt(b"", b"", 1000, 1, 1, &mut output).is_err());
+		assert!(scrypt(b"", b"", 1024, 0, 1, &mut output).is_err());
+		assert!(scrypt(b"", b"", 1024, 1, 0, &mut output).is_err());
+		assert!(scrypt(b"", b"", 1 << 31, 8, 1, &mut output).is_err());
+		assert!(scrypt(b"", b"", 2, 1 << 22, 64, &mut output).is_err());
+	}
+}
@@ -0,0 +1,177 @@
+//! Recovery of secret with a few unknown hex characters, given its address.
+
+use std::str::FromStr;
+use std::cmp;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use incremental::run_workers;
+use super::{Progress, KeyPair, Secret, Address, Error};
+
+/// Number of candidates claimed by worker at once.
+const CHUNK: usize = 64;
+
+/// Secret hex with unknown nibbles marked by `?`, eg. `a100df7a??8e50...`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretTemplate {
+	known: [u8; 32],
+	/// Positions of unknown nibbles, most significant first.
+	unknown: Vec<usize>,
+}
+
+impl FromStr for SecretTemplate {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = if s.starts_with("0x") { &s[2..] } else { s };
+		if s.len() != 64 {
+			return Err(Error::Custom(format!("Secret template must have 64 hex characters, got {}", s.len())));
+		}
+
+		let mut known = [0u8; 32];
+		let mut unknown = Vec::new();
+		for (i, c) in s.chars().enumerate() {
+			match c {
+				'?' => unknown.push(i),
+				_ => {
+					let nibble = try!(c.to_digit(16).ok_or_else(|| Error::Custom(format!("Invalid secret template character: {}", c))));
+					known[i / 2] |= (nibble as u8) << (4 * (1 - i % 2));
+				},
+			}
+		}
+
+		// every candidate index must fit usize
+		if (unknown.len() * 4) as u32 >= usize::max_value().count_ones() {
+			return Err(Error::Custom(format!("Too many unknown nibbles: {}", unknown.len())));
+		}
+
+		Ok(SecretTemplate {
+			known: known,
+			unknown: unknown,
+		})
+	}
+}
+
+impl SecretTemplate {
+	/// Number of unknown nibbles.
+	pub fn unknown(&self) -> usize {
+		self.unknown.len()
+	}
+
+	/// Number of candidate secrets.
+	pub fn size(&self) -> usize {
+		1 << (4 * self.unknown.len())
+	}
+
+	/// Returns candidate with unknown nibbles set to the nibbles of `index`.
+	pub fn candidate(&self, index: usize) -> [u8; 32] {
+		let mut result = self.known;
+		for (i, position) in self.unknown.iter().rev().enumerate() {
+			let nibble = ((index >> (4 * i)) & 0xf) as u8;
+			result[position / 2] |= nibble << (4 * (1 - position % 2));
+		}
+		result
+	}
+}
+
+/// Searches all candidates of the template for secret of the address, on multiple threads.
+pub struct SecretRecovery {
+	template: SecretTemplate,
+	address: Address,
+	threads: usize,
+	progress: Progress,
+}
+
+impl SecretRecovery {
+	pub fn new(template: SecretTemplate, address: Address, threads: usize) -> Self {
+		SecretRecovery::with_progress(template, address, threads, Progress::new())
+	}
+
+	pub fn with_progress(template: SecretTemplate, address: Address, threads: usize, progress: Progress) -> Self {
+		SecretRecovery {
+			template: template,
+			address: address,
+			threads: cmp::max(threads, 1),
+			progress: progress,
+		}
+	}
+
+	/// Number of candidate secrets.
+	pub fn size(&self) -> usize {
+		self.template.size()
+	}
+
+	/// Returns keypair of the recovered secret.
+	pub fn recover(self) -> Result<KeyPair, Error> {
+		let (template, address, progress) = (self.template, self.address, self.progress.clone());
+		let next = AtomicUsize::new(0);
+		let found = try!(run_workers(self.threads, &self.progress, move |done| search(&template, &address, &next, done, &progress).map(Ok)));
+		found.ok_or_else(|| Error::Custom("Could not recover secret".into()))
+	}
+}
+
+/// Tries chunks of candidates claimed from `next` until address matches, `done` is set, `progress` is cancelled or candidates run out.
+fn search(template: &SecretTemplate, address: &Address, next: &AtomicUsize, done: &AtomicBool, progress: &Progress) -> Option<KeyPair> {
+	let size = template.size();
+	while !done.load(Ordering::Relaxed) && !progress.is_cancelled() {
+		let claimed = next.fetch_add(CHUNK, Ordering::Relaxed);
+		if claimed >= size {
+			return None;
+		}
+
+		let end = cmp::min(claimed + CHUNK, size);
+		progress.counter().fetch_add(end - claimed, Ordering::Relaxed);
+		for index in claimed..end {
+			// zero and values above the curve order are not valid secrets
+			match KeyPair::from_secret(Secret::from(template.candidate(index))) {
+				Ok(ref keypair) if keypair.address() != *address => (),
+				Ok(keypair) => return Some(keypair),
+				Err(_) => (),
+			}
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::ToHex;
+	use {Address, Progress};
+	use super::{SecretTemplate, SecretRecovery};
+
+	const SECRET: &'static str = "17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55";
+
+	#[test]
+	fn template() {
+		let template = SecretTemplate::from_str("0x17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be?5").unwrap();
+		assert_eq!(template.unknown(), 1);
+		assert_eq!(template.size(), 16);
+		assert_eq!(template.candidate(5).to_hex(), SECRET);
+
+		let template = SecretTemplate::from_str("?7d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be5?").unwrap();
+		assert_eq!(template.candidate(0x15).to_hex(), SECRET);
+
+		assert!(SecretTemplate::from_str("17d08f").is_err());
+		assert!(SecretTemplate::from_str(&SECRET.replace("f", "g")).is_err());
+		assert!(SecretTemplate::from_str(&"?".repeat(64)).is_err());
+	}
+
+	#[test]
+	fn recover_secret() {
+		let address = Address::from_str("26d1eC50B4e62c1d1a40D16E7cacc6A6580757d5").unwrap();
+		let template = SecretTemplate::from_str("17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0b??5").unwrap();
+		let progress = Progress::new();
+		let keypair = SecretRecovery::with_progress(template, address.clone(), 2, progress.clone()).recover().unwrap();
+		assert_eq!(keypair.secret().to_hex(), SECRET);
+		assert_eq!(keypair.address(), address);
+		assert!(progress.attempts() > 0 && progress.attempts() <= 256);
+	}
+
+	#[test]
+	fn recover_secret_fails() {
+		let template = SecretTemplate::from_str("17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be5?").unwrap();
+		let progress = Progress::new();
+		assert!(SecretRecovery::with_progress(template, Address::default(), 2, progress.clone()).recover().is_err());
+		assert_eq!(progress.attempts(), 16);
+	}
+}
@@ -25,6 +25,26 @@ impl Signature {
 	pub fn v(&self) -> u8 {
 		self.0[64]
 	}
+
+	/// Parse signature with the recovery byte in "Electrum" notation (27 or 28).
+	/// Returns empty (invalid) signature if data has invalid length or recovery byte.
+	pub fn from_electrum(data: &[u8]) -> Self {
+		if data.len() != 65 || data[64] < 27 {
+			return Signature::default();
+		}
+
+		let mut sig = [0u8; 65];
+		sig.copy_from_slice(data);
+		sig[64] -= 27;
+		Signature(sig)
+	}
+
+	/// Convert into signature with the recovery byte in "Electrum" notation (27 or 28),
+	/// as used by `eth_sign` and wallets.
+	pub fn into_electrum(mut self) -> [u8; 65] {
+		self.0[64] += 27;
+		self.0
+	}
 }
 
 // manual implementation large arrays don't have trait impls by default.
@@ -35,6 +55,12 @@ impl ::std::cmp::PartialEq for Signature {
 	}
 }
 
+impl Clone for Signature {
+	fn clone(&self) -> Self {
+		Signature(self.0)
+	}
+}
+
 // also manual for the same reason, but the pretty printing might be useful.
 impl fmt::Debug for Signature {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -0,0 +1,586 @@
+//! Ethereum transactions signing.
+
+use std::str::FromStr;
+use std::u64;
+use rustc_serialize::hex::FromHex;
+use rustc_serialize::json::Json;
+use keccak::Keccak256;
+use rlp::{RlpStream, Rlp};
+use super::{Secret, Address, Message, Signature, Error, sign, recover, public_to_address};
+
+fn error<T>(msg: &str) -> Result<T, Error> {
+	Err(Error::InvalidTransaction(msg.into()))
+}
+
+/// Parses unsigned integer given as json number, decimal or 0x prefixed hex string.
+fn json_uint(json: &Json, field: &str) -> Result<Option<u128>, Error> {
+	let value = match json.find(field) {
+		None | Some(&Json::Null) => return Ok(None),
+		Some(&Json::U64(n)) => return Ok(Some(n as u128)),
+		Some(&Json::String(ref s)) => s,
+		Some(_) => return Err(Error::InvalidTransaction(format!("invalid {}", field))),
+	};
+
+	let parsed = match value.starts_with("0x") {
+		true => u128::from_str_radix(&value[2..], 16),
+		false => u128::from_str_radix(value, 10),
+	};
+	parsed.map(Some).or_else(|_| Err(Error::InvalidTransaction(format!("invalid {}", field))))
+}
+
+fn json_u64(json: &Json, field: &str) -> Result<Option<u64>, Error> {
+	match try!(json_uint(json, field)) {
+		Some(n) if n > u64::MAX as u128 => Err(Error::InvalidTransaction(format!("invalid {}", field))),
+		n => Ok(n.map(|n| n as u64)),
+	}
+}
+
+/// Largest chain id, for which EIP-155 `v` fits u64.
+const MAX_CHAIN_ID: u64 = (u64::MAX - 36) / 2;
+
+fn json_bytes(json: &Json, field: &str) -> Result<Vec<u8>, Error> {
+	match json.find(field) {
+		None | Some(&Json::Null) => Ok(Vec::new()),
+		Some(&Json::String(ref s)) if s.starts_with("0x") => s[2..].from_hex().or_else(|_| Err(Error::InvalidTransaction(format!("invalid {}", field)))),
+		Some(_) => Err(Error::InvalidTransaction(format!("invalid {}", field))),
+	}
+}
+
+/// EIP-2718 transaction type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+	/// Legacy transaction, optionally with EIP-155 replay protection.
+	Legacy,
+	/// EIP-2930 transaction with access list, type 1.
+	AccessList,
+	/// EIP-1559 transaction with dynamic fee, type 2.
+	DynamicFee,
+}
+
+impl Default for TransactionType {
+	fn default() -> Self {
+		TransactionType::Legacy
+	}
+}
+
+impl TransactionType {
+	/// Returns EIP-2718 type byte, `None` for legacy transaction.
+	pub fn type_byte(&self) -> Option<u8> {
+		match *self {
+			TransactionType::Legacy => None,
+			TransactionType::AccessList => Some(1),
+			TransactionType::DynamicFee => Some(2),
+		}
+	}
+}
+
+/// EIP-2930 access list entry.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccessListItem {
+	/// Accessed account.
+	pub address: Address,
+	/// Accessed storage keys of the account.
+	pub storage_keys: Vec<[u8; 32]>,
+}
+
+/// Unsigned transaction.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Transaction {
+	/// Transaction type.
+	pub transaction_type: TransactionType,
+	/// Sender nonce.
+	pub nonce: u64,
+	/// Gas price in wei, used by legacy and access list transactions.
+	pub gas_price: u128,
+	/// Maximum total fee per gas in wei, used by dynamic fee transactions.
+	pub max_fee_per_gas: u128,
+	/// Maximum priority fee per gas in wei, used by dynamic fee transactions.
+	pub max_priority_fee_per_gas: u128,
+	/// Gas limit.
+	pub gas: u64,
+	/// Recipient, `None` for contract creation.
+	pub to: Option<Address>,
+	/// Transferred value in wei.
+	pub value: u128,
+	/// Call data or contract init code.
+	pub data: Vec<u8>,
+	/// Accessed accounts and storage keys, not used by legacy transactions.
+	pub access_list: Vec<AccessListItem>,
+	/// Chain id, `None` for pre-EIP-155 legacy transaction. Required by typed transactions.
+	pub chain_id: Option<u64>,
+}
+
+impl Transaction {
+	fn append_fields(&self, stream: &mut RlpStream) {
+		if self.transaction_type != TransactionType::Legacy {
+			stream.append_uint(self.chain_id.unwrap_or(0) as u128);
+		}
+
+		stream.append_uint(self.nonce as u128);
+		match self.transaction_type {
+			TransactionType::DynamicFee => stream.append_uint(self.max_priority_fee_per_gas).append_uint(self.max_fee_per_gas),
+			_ => stream.append_uint(self.gas_price),
+		};
+		stream.append_uint(self.gas as u128);
+		match self.to {
+			Some(ref to) => stream.append_bytes(&to[..]),
+			None => stream.append_bytes(&[]),
+		};
+		stream
+			.append_uint(self.value)
+			.append_bytes(&self.data);
+
+		if self.transaction_type != TransactionType::Legacy {
+			let mut access_list = RlpStream::new();
+			for item in &self.access_list {
+				let mut storage_keys = RlpStream::new();
+				for key in &item.storage_keys {
+					storage_keys.append_bytes(key);
+				}
+				let mut entry = RlpStream::new();
+				entry.append_bytes(&item.address[..]).append_list(&storage_keys);
+				access_list.append_list(&entry);
+			}
+			stream.append_list(&access_list);
+		}
+	}
+
+	/// Prepends EIP-2718 type byte to the encoded payload.
+	fn envelope(&self, payload: Vec<u8>) -> Vec<u8> {
+		match self.transaction_type.type_byte() {
+			Some(type_byte) => {
+				let mut result = vec![type_byte];
+				result.extend(payload);
+				result
+			},
+			None => payload,
+		}
+	}
+
+	/// Returns hash to be signed. Includes chain id as defined by EIP-155,
+	/// typed transactions are prefixed with type byte as defined by EIP-2718.
+	pub fn signing_hash(&self) -> Message {
+		let mut stream = RlpStream::new();
+		self.append_fields(&mut stream);
+		if let (TransactionType::Legacy, Some(chain_id)) = (self.transaction_type, self.chain_id) {
+			stream.append_uint(chain_id as u128).append_uint(0).append_uint(0);
+		}
+		Message::from(self.envelope(stream.out()).keccak256())
+	}
+
+	/// Signs the transaction.
+	pub fn sign(self, secret: &Secret) -> Result<SignedTransaction, Error> {
+		if self.transaction_type != TransactionType::Legacy && self.chain_id.is_none() {
+			return error("typed transaction requires chain id");
+		}
+		if self.chain_id.map_or(false, |chain_id| chain_id > MAX_CHAIN_ID) {
+			return error("invalid chainId");
+		}
+
+		let signature = try!(sign(secret, &self.signing_hash()));
+		Ok(SignedTransaction {
+			transaction: self,
+			signature: signature,
+		})
+	}
+}
+
+impl FromStr for Transaction {
+	type Err = Error;
+
+	/// Parses json with `type`, `nonce`, `gasPrice`, `maxFeePerGas`, `maxPriorityFeePerGas`,
+	/// `gas` (or `gasLimit`), `to`, `value`, `data`, `accessList` and `chainId`.
+	/// Missing numbers default to zero, missing `to` means contract creation.
+	/// Missing type is inferred from the fee and access list fields.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let json = try!(Json::from_str(s).or_else(|_| error("invalid json")));
+		if !json.is_object() {
+			return error("expected json object");
+		}
+
+		let transaction_type = match try!(json_u64(&json, "type")) {
+			Some(0) => TransactionType::Legacy,
+			Some(1) => TransactionType::AccessList,
+			Some(2) => TransactionType::DynamicFee,
+			Some(_) => return error("unsupported type"),
+			None if json.find("maxFeePerGas").is_some() => TransactionType::DynamicFee,
+			None if json.find("accessList").is_some() => TransactionType::AccessList,
+			None => TransactionType::Legacy,
+		};
+
+		let mut access_list = Vec::new();
+		match json.find("accessList") {
+			None | Some(&Json::Null) => {},
+			Some(&Json::Array(ref items)) => for item in items {
+				let address = match item.find("address").and_then(Json::as_string) {
+					Some(address) => try!(Address::from_str(address)),
+					None => return error("invalid accessList"),
+				};
+
+				let mut storage_keys = Vec::new();
+				for key in item.find("storageKeys").and_then(Json::as_array).map_or(&[][..], |keys| &keys[..]) {
+					let key = match key.as_string() {
+						Some(key) if key.starts_with("0x") => try!(key[2..].from_hex().or_else(|_| error("invalid storage key"))),
+						_ => return error("invalid storage key"),
+					};
+					if key.len() != 32 {
+						return error("invalid storage key");
+					}
+					let mut storage_key = [0u8; 32];
+					storage_key.copy_from_slice(&key);
+					storage_keys.push(storage_key);
+				}
+
+				access_list.push(AccessListItem {
+					address: address,
+					storage_keys: storage_keys,
+				});
+			},
+			Some(_) => return error("invalid accessList"),
+		}
+
+		let gas = match try!(json_u64(&json, "gas")) {
+			Some(gas) => Some(gas),
+			None => try!(json_u64(&json, "gasLimit")),
+		};
+
+		let to = match json.find("to") {
+			None | Some(&Json::Null) => None,
+			Some(&Json::String(ref s)) if s.is_empty() || s == "0x" => None,
+			Some(&Json::String(ref s)) => Some(try!(Address::from_str(s))),
+			Some(_) => return error("invalid to"),
+		};
+
+		let chain_id = try!(json_u64(&json, "chainId"));
+		if chain_id.map_or(false, |chain_id| chain_id > MAX_CHAIN_ID) {
+			return error("invalid chainId");
+		}
+
+		Ok(Transaction {
+			transaction_type: transaction_type,
+			nonce: try!(json_u64(&json, "nonce")).unwrap_or(0),
+			gas_price: try!(json_uint(&json, "gasPrice")).unwrap_or(0),
+			max_fee_per_gas: try!(json_uint(&json, "maxFeePerGas")).unwrap_or(0),
+			max_priority_fee_per_gas: try!(json_uint(&json, "maxPriorityFeePerGas")).unwrap_or(0),
+			gas: gas.unwrap_or(0),
+			to: to,
+			value: try!(json_uint(&json, "value")).unwrap_or(0),
+			data: try!(json_bytes(&json, "data")),
+			access_list: access_list,
+			chain_id: chain_id,
+		})
+	}
+}
+
+/// Signed transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTransaction {
+	/// Signed transaction.
+	pub transaction: Transaction,
+	/// Signature with recovery byte 0 or 1.
+	pub signature: Signature,
+}
+
+impl SignedTransaction {
+	/// Returns `v` as included in the transaction, `27 + recovery id` or EIP-155 `chain_id * 2 + 35 + recovery id`
+	/// for legacy transactions and y parity for typed transactions.
+	pub fn v(&self) -> u64 {
+		let recovery = self.signature.v() as u64;
+		match (self.transaction.transaction_type, self.transaction.chain_id) {
+			(TransactionType::Legacy, Some(chain_id)) => chain_id * 2 + 35 + recovery,
+			(TransactionType::Legacy, None) => 27 + recovery,
+			_ => recovery,
+		}
+	}
+
+	/// Returns raw transaction bytes, as accepted by `eth_sendRawTransaction`.
+	pub fn raw(&self) -> Vec<u8> {
+		let mut stream = RlpStream::new();
+		self.transaction.append_fields(&mut stream);
+		stream
+			.append_uint(self.v() as u128)
+			.append_scalar(self.signature.r())
+			.append_scalar(self.signature.s());
+		self.transaction.envelope(stream.out())
+	}
+
+	/// Decodes raw legacy or EIP-2718 typed transaction.
+	pub fn decode(raw: &[u8]) -> Result<Self, Error> {
+		let (transaction_type, payload) = match raw.first() {
+			Some(&1) => (TransactionType::AccessList, &raw[1..]),
+			Some(&2) => (TransactionType::DynamicFee, &raw[1..]),
+			Some(&first) if first >= 0xc0 => (TransactionType::Legacy, raw),
+			_ => return error("unsupported type"),
+		};
+
+		let items = try!(try!(Rlp::new(payload)).items());
+		let expected = match transaction_type {
+			TransactionType::Legacy => 9,
+			TransactionType::AccessList => 11,
+			TransactionType::DynamicFee => 12,
+		};
+		if items.len() != expected {
+			return error("invalid number of fields");
+		}
+
+		let mut transaction = Transaction {
+			transaction_type: transaction_type,
+			..Default::default()
+		};
+
+		let mut fields = items.iter();
+		let mut next = || fields.next().expect("number of fields checked above; qed");
+		if transaction_type != TransactionType::Legacy {
+			transaction.chain_id = Some(try!(next().as_u64()));
+		}
+		transaction.nonce = try!(next().as_u64());
+		if transaction_type == TransactionType::DynamicFee {
+			transaction.max_priority_fee_per_gas = try!(next().as_uint());
+			transaction.max_fee_per_gas = try!(next().as_uint());
+		} else {
+			transaction.gas_price = try!(next().as_uint());
+		}
+		transaction.gas = try!(next().as_u64());
+		transaction.to = match try!(next().data()) {
+			to if to.is_empty() => None,
+			to if to.len() == 20 => {
+				let mut address = Address::default();
+				address.copy_from_slice(to);
+				Some(address)
+			},
+			_ => return error("invalid to"),
+		};
+		transaction.value = try!(next().as_uint());
+		transaction.data = try!(next().data()).to_vec();
+
+		if transaction_type != TransactionType::Legacy {
+			for entry in try!(next().items()) {
+				let entry = try!(entry.items());
+				if entry.len() != 2 {
+					return error("invalid accessList");
+				}
+				let address = try!(entry[0].data());
+				if address.len() != 20 {
+					return error("invalid accessList");
+				}
+				let mut item = AccessListItem::default();
+				item.address.copy_from_slice(address);
+				for key in try!(entry[1].items()) {
+					let key = try!(key.data());
+					if key.len() != 32 {
+						return error("invalid storage key");
+					}
+					let mut storage_key = [0u8; 32];
+					storage_key.copy_from_slice(key);
+					item.storage_keys.push(storage_key);
+				}
+				transaction.access_list.push(item);
+			}
+		}
+
+		let v = try!(next().as_u64());
+		let recovery = match transaction_type {
+			TransactionType::Legacy if v == 27 || v == 28 => v - 27,
+			TransactionType::Legacy if v >= 35 => {
+				transaction.chain_id = Some((v - 35) / 2);
+				(v - 35) % 2
+			},
+			TransactionType::Legacy => return error("invalid v"),
+			_ if v <= 1 => v,
+			_ => return error("invalid y parity"),
+		};
+
+		let mut signature = [0u8; 65];
+		for &(item, offset) in &[(next(), 0), (next(), 32)] {
+			let scalar = try!(item.as_scalar());
+			if scalar.len() > 32 {
+				return error("invalid signature");
+			}
+			signature[offset + 32 - scalar.len()..offset + 32].copy_from_slice(scalar);
+		}
+		signature[64] = recovery as u8;
+
+		Ok(SignedTransaction {
+			transaction: transaction,
+			signature: Signature::from(signature),
+		})
+	}
+
+	/// Returns transaction hash.
+	pub fn hash(&self) -> Message {
+		Message::from(self.raw().keccak256())
+	}
+
+	/// Recovers address of the transaction signer.
+	pub fn sender(&self) -> Result<Address, Error> {
+		let public = try!(recover(&self.signature, &self.transaction.signing_hash()));
+		Ok(public_to_address(&public))
+	}
+}
+
+/// Decodes raw legacy, EIP-155, EIP-2930 or EIP-1559 transaction and recovers its sender.
+pub fn recover_sender(raw: &[u8]) -> Result<(SignedTransaction, Address), Error> {
+	let signed = try!(SignedTransaction::decode(raw));
+	let sender = try!(signed.sender());
+	Ok((signed, sender))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use {Secret, Address, KeyPair};
+	use super::{Transaction, TransactionType, AccessListItem, SignedTransaction, recover_sender};
+
+	fn eip155_transaction() -> Transaction {
+		Transaction {
+			nonce: 9,
+			gas_price: 20_000_000_000,
+			gas: 21000,
+			to: Some(Address::from_str("3535353535353535353535353535353535353535").unwrap()),
+			value: 1_000_000_000_000_000_000,
+			chain_id: Some(1),
+			..Default::default()
+		}
+	}
+
+	fn secret() -> Secret {
+		Secret::from_str("4646464646464646464646464646464646464646464646464646464646464646").unwrap()
+	}
+
+	#[test]
+	fn eip155_example() {
+		let transaction = eip155_transaction();
+		assert_eq!(transaction.signing_hash().to_hex(), "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53");
+
+		let signed = transaction.sign(&secret()).unwrap();
+		assert_eq!(signed.v(), 37);
+		assert_eq!(signed.raw().to_hex(), "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83");
+		assert_eq!(signed.hash().to_hex(), "33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788");
+	}
+
+	#[test]
+	fn pre_eip155_signing_hash() {
+		let mut transaction = eip155_transaction();
+		transaction.chain_id = None;
+		let signed = transaction.sign(&secret()).unwrap();
+		assert!(signed.v() == 27 || signed.v() == 28);
+		assert_eq!(signed.raw()[0], 0xf8);
+	}
+
+	#[test]
+	fn transaction_from_json() {
+		let json = r#"{
+			"nonce": 9,
+			"gasPrice": "20000000000",
+			"gasLimit": "0x5208",
+			"to": "0x3535353535353535353535353535353535353535",
+			"value": "1000000000000000000",
+			"data": "0x",
+			"chainId": 1
+		}"#;
+		assert_eq!(Transaction::from_str(json).unwrap(), eip155_transaction());
+
+		let creation = Transaction::from_str(r#"{ "gas": 100000, "data": "0x6000" }"#).unwrap();
+		assert_eq!(creation.to, None);
+		assert_eq!(creation.data, vec![0x60, 0x00]);
+		assert_eq!(creation.chain_id, None);
+
+		assert!(Transaction::from_str(r#"{ "nonce": "0xzz" }"#).is_err());
+		assert!(Transaction::from_str(r#"{ "to": "0x35" }"#).is_err());
+		assert!(Transaction::from_str("[]").is_err());
+		assert!(Transaction::from_str(r#"{ "chainId": "0xffffffffffffffff" }"#).is_err());
+		assert!(Transaction::from_str(r#"{ "chainId": "0x7fffffffffffffed" }"#).is_ok());
+
+		let mut transaction = eip155_transaction();
+		transaction.chain_id = Some(u64::MAX);
+		assert!(transaction.sign(&secret()).is_err());
+	}
+
+	#[test]
+	fn decode_eip155_example() {
+		let raw = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83".from_hex().unwrap();
+		let decoded = SignedTransaction::decode(&raw).unwrap();
+		assert_eq!(decoded.transaction, eip155_transaction());
+		assert_eq!(decoded, eip155_transaction().sign(&secret()).unwrap());
+		assert_eq!(decoded.raw(), raw);
+	}
+
+	#[test]
+	fn access_list_transaction() {
+		let mut transaction = eip155_transaction();
+		transaction.transaction_type = TransactionType::AccessList;
+		transaction.access_list = vec![AccessListItem {
+			address: Address::from_str("3535353535353535353535353535353535353535").unwrap(),
+			storage_keys: vec![[0u8; 32], [1u8; 32]],
+		}];
+
+		let signed = transaction.clone().sign(&secret()).unwrap();
+		let raw = signed.raw();
+		assert_eq!(raw[0], 1);
+		assert!(signed.v() <= 1);
+		assert_eq!(SignedTransaction::decode(&raw).unwrap(), signed);
+		assert!(transaction.signing_hash() != eip155_transaction().signing_hash());
+	}
+
+	#[test]
+	fn dynamic_fee_transaction() {
+		let json = r#"{
+			"chainId": "0x1",
+			"nonce": "0x0",
+			"maxPriorityFeePerGas": "1000000000",
+			"maxFeePerGas": "30000000000",
+			"gas": 21000,
+			"to": "0x3535353535353535353535353535353535353535",
+			"value": "0x1",
+			"accessList": []
+		}"#;
+		let transaction = Transaction::from_str(json).unwrap();
+		assert_eq!(transaction.transaction_type, TransactionType::DynamicFee);
+		assert_eq!(transaction.max_fee_per_gas, 30_000_000_000);
+
+		let signed = transaction.clone().sign(&secret()).unwrap();
+		let raw = signed.raw();
+		// type byte followed by list of 12 fields: chain id, nonce, fees, gas, to, value, data, access list, y parity, r and s
+		assert_eq!(raw[..2].to_hex(), "02f8");
+		assert_eq!(SignedTransaction::decode(&raw).unwrap(), signed);
+
+		let mut unprotected = transaction;
+		unprotected.chain_id = None;
+		assert!(unprotected.sign(&secret()).is_err());
+	}
+
+	#[test]
+	fn decode_invalid() {
+		assert!(SignedTransaction::decode(&[]).is_err());
+		assert!(SignedTransaction::decode(&[3, 0xc0]).is_err());
+		assert!(SignedTransaction::decode(&"c0".from_hex().unwrap()).is_err());
+		let mut raw = eip155_transaction().sign(&secret()).unwrap().raw();
+		raw.push(0);
+		assert!(SignedTransaction::decode(&raw).is_err());
+	}
+
+	#[test]
+	fn recover_eip155_sender() {
+		let raw = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83".from_hex().unwrap();
+		let (signed, sender) = recover_sender(&raw).unwrap();
+		assert_eq!(sender.to_checksum(), "9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F");
+		assert_eq!(signed.transaction.chain_id, Some(1));
+	}
+
+	#[test]
+	fn recover_typed_senders() {
+		let expected = KeyPair::from_secret(secret()).unwrap().address();
+		let mut transaction = eip155_transaction();
+		for transaction_type in &[TransactionType::Legacy, TransactionType::AccessList, TransactionType::DynamicFee] {
+			transaction.transaction_type = *transaction_type;
+			let raw = transaction.clone().sign(&secret()).unwrap().raw();
+			assert_eq!(recover_sender(&raw).unwrap().1, expected);
+		}
+
+		transaction.chain_id = None;
+		transaction.transaction_type = TransactionType::Legacy;
+		let raw = transaction.sign(&secret()).unwrap().raw();
+		assert_eq!(recover_sender(&raw).unwrap().1, expected);
+	}
+}
@@ -0,0 +1,470 @@
+//! EIP-712 typed structured data hashing.
+
+use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet};
+use rustc_serialize::hex::FromHex;
+use rustc_serialize::json::Json;
+use keccak::Keccak256;
+use super::{Secret, Public, Message, Signature, Error, sign, recover};
+
+const DOMAIN_TYPE: &'static str = "EIP712Domain";
+
+fn error<T>(msg: &str) -> Result<T, Error> {
+	Err(Error::InvalidTypedData(msg.into()))
+}
+
+/// Single struct member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Member {
+	name: String,
+	kind: String,
+}
+
+/// Typed data document as accepted by `eth_signTypedData_v4`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedData {
+	types: BTreeMap<String, Vec<Member>>,
+	primary_type: String,
+	domain: Json,
+	message: Json,
+}
+
+/// Strips array suffixes, eg. `Person[][3]` becomes `Person`.
+fn base_type(kind: &str) -> &str {
+	match kind.find('[') {
+		Some(position) => &kind[..position],
+		None => kind,
+	}
+}
+
+/// Splits array type into element type and optional fixed length.
+fn array_type(kind: &str) -> Option<(&str, Option<usize>)> {
+	if !kind.ends_with(']') {
+		return None;
+	}
+
+	kind.rfind('[').map(|position| {
+		let len = usize::from_str(&kind[position + 1..kind.len() - 1]).ok();
+		(&kind[..position], len)
+	})
+}
+
+fn hex_value(value: &Json) -> Result<Vec<u8>, Error> {
+	match value.as_string() {
+		Some(s) if s.starts_with("0x") => s[2..].from_hex().or_else(|_| error("invalid hex value")),
+		_ => error("expected 0x prefixed hex value"),
+	}
+}
+
+/// Multiplies big endian number by `mul` and adds `add`. Returns false on overflow.
+fn mul_add(number: &mut [u8; 32], mul: u32, add: u32) -> bool {
+	let mut carry = add;
+	for byte in number.iter_mut().rev() {
+		let value = (*byte as u32) * mul + carry;
+		*byte = value as u8;
+		carry = value >> 8;
+	}
+	carry == 0
+}
+
+fn negate(number: &mut [u8; 32]) {
+	for byte in number.iter_mut() {
+		*byte = !*byte;
+	}
+	for byte in number.iter_mut().rev() {
+		*byte = byte.wrapping_add(1);
+		if *byte != 0 {
+			break;
+		}
+	}
+}
+
+/// Number of significant bits of big endian number.
+fn bit_len(number: &[u8; 32]) -> usize {
+	match number.iter().position(|byte| *byte != 0) {
+		Some(i) => (32 - i) * 8 - number[i].leading_zeros() as usize,
+		None => 0,
+	}
+}
+
+/// Encodes `intN` or `uintN` value given as json number, decimal or 0x prefixed hex string.
+fn encode_integer(value: &Json, kind: &str) -> Result<[u8; 32], Error> {
+	let (signed, bits) = match kind.starts_with("int") {
+		true => (true, &kind[3..]),
+		false => (false, &kind[4..]),
+	};
+	let bits = match bits {
+		"" => 256,
+		bits => match usize::from_str(bits) {
+			Ok(bits) if bits > 0 && bits <= 256 && bits % 8 == 0 => bits,
+			_ => return Err(Error::InvalidTypedData(format!("unknown type {}", kind))),
+		},
+	};
+
+	let (negative, digits) = match *value {
+		Json::U64(n) => (false, n.to_string()),
+		Json::I64(n) if n < 0 => (true, n.to_string()[1..].to_owned()),
+		Json::I64(n) => (false, n.to_string()),
+		Json::String(ref s) if s.starts_with('-') => (true, s[1..].to_owned()),
+		Json::String(ref s) => (false, s.clone()),
+		_ => return error("expected integer value"),
+	};
+
+	if negative && !signed {
+		return error("negative value of unsigned integer");
+	}
+
+	let mut result = [0u8; 32];
+	let (radix, digits) = match digits.starts_with("0x") {
+		true => (16, &digits[2..]),
+		false => (10, &digits[..]),
+	};
+
+	if digits.is_empty() {
+		return error("expected integer value");
+	}
+
+	for c in digits.chars() {
+		let digit = match c.to_digit(radix) {
+			Some(digit) => digit,
+			None => return error("invalid integer value"),
+		};
+		if !mul_add(&mut result, radix, digit) {
+			return error("integer overflow");
+		}
+	}
+
+	// signed values hold `bits - 1` bits of magnitude, except for the lowest negative one
+	let magnitude = if signed { bits - 1 } else { bits };
+	let lowest = negative && bit_len(&result) == magnitude + 1 && result.iter().map(|byte| byte.count_ones()).sum::<u32>() == 1;
+	if bit_len(&result) > magnitude && !lowest {
+		return Err(Error::InvalidTypedData(format!("value out of range of {}", kind)));
+	}
+
+	if negative {
+		negate(&mut result);
+	}
+	Ok(result)
+}
+
+impl TypedData {
+	/// Type of the signed message.
+	pub fn primary_type(&self) -> &str {
+		&self.primary_type
+	}
+
+	fn members(&self, kind: &str) -> Result<&[Member], Error> {
+		match self.types.get(kind) {
+			Some(members) => Ok(members),
+			None => Err(Error::InvalidTypedData(format!("unknown type {}", kind))),
+		}
+	}
+
+	fn dependencies(&self, kind: &str, found: &mut BTreeSet<String>) {
+		let kind = base_type(kind);
+		if found.contains(kind) || !self.types.contains_key(kind) {
+			return;
+		}
+
+		found.insert(kind.to_owned());
+		for member in &self.types[kind] {
+			self.dependencies(&member.kind, found);
+		}
+	}
+
+	/// Returns `encodeType` of the struct, eg. `Mail(Person from,Person to,string contents)Person(string name,address wallet)`.
+	pub fn encode_type(&self, kind: &str) -> Result<String, Error> {
+		try!(self.members(kind));
+		let mut dependencies = BTreeSet::new();
+		self.dependencies(kind, &mut dependencies);
+		dependencies.remove(kind);
+
+		let mut result = String::new();
+		for name in Some(kind.to_owned()).into_iter().chain(dependencies.into_iter()) {
+			let members = self.types[&name].iter()
+				.map(|member| format!("{} {}", member.kind, member.name))
+				.collect::<Vec<_>>();
+			result.push_str(&format!("{}({})", name, members.join(",")));
+		}
+		Ok(result)
+	}
+
+	/// Returns `typeHash` of the struct.
+	pub fn type_hash(&self, kind: &str) -> Result<[u8; 32], Error> {
+		Ok(try!(self.encode_type(kind)).as_bytes().keccak256())
+	}
+
+	fn encode_value(&self, kind: &str, value: &Json) -> Result<[u8; 32], Error> {
+		if let Some((element, len)) = array_type(kind) {
+			let items = match value.as_array() {
+				Some(items) => items,
+				None => return error("expected array value"),
+			};
+			if len.map_or(false, |len| len != items.len()) {
+				return error("invalid fixed array length");
+			}
+
+			let mut encoded = Vec::with_capacity(items.len() * 32);
+			for item in items {
+				encoded.extend_from_slice(&try!(self.encode_value(element, item)));
+			}
+			return Ok(encoded.keccak256());
+		}
+
+		if self.types.contains_key(kind) {
+			return self.hash_struct(kind, value);
+		}
+
+		let mut result = [0u8; 32];
+		match kind {
+			"string" => match value.as_string() {
+				Some(s) => result = s.as_bytes().keccak256(),
+				None => return error("expected string value"),
+			},
+			"bytes" => result = try!(hex_value(value)).keccak256(),
+			"bool" => match value.as_boolean() {
+				Some(b) => result[31] = b as u8,
+				None => return error("expected bool value"),
+			},
+			"address" => {
+				let address = try!(hex_value(value));
+				if address.len() != 20 {
+					return error("invalid address value");
+				}
+				result[12..32].copy_from_slice(&address);
+			},
+			_ if kind.starts_with("bytes") => {
+				let bytes = try!(hex_value(value));
+				match usize::from_str(&kind[5..]) {
+					Ok(len) if len > 0 && len <= 32 && bytes.len() == len => result[..len].copy_from_slice(&bytes),
+					_ => return error("invalid fixed bytes value"),
+				}
+			},
+			_ if kind.starts_with("uint") || kind.starts_with("int") => {
+				result = try!(encode_integer(value, kind));
+			},
+			_ => return Err(Error::InvalidTypedData(format!("unknown type {}", kind))),
+		}
+		Ok(result)
+	}
+
+	/// Returns `hashStruct` of the value.
+	pub fn hash_struct(&self, kind: &str, value: &Json) -> Result<[u8; 32], Error> {
+		let mut encoded = try!(self.type_hash(kind)).to_vec();
+		for member in try!(self.members(kind)) {
+			let member_value = match value.find(&member.name) {
+				Some(member_value) => member_value,
+				None => return Err(Error::InvalidTypedData(format!("missing value of {}", member.name))),
+			};
+			encoded.extend_from_slice(&try!(self.encode_value(&member.kind, member_value)));
+		}
+		Ok(encoded.keccak256())
+	}
+
+	/// Returns `hashStruct` of the domain.
+	pub fn domain_separator(&self) -> Result<[u8; 32], Error> {
+		self.hash_struct(DOMAIN_TYPE, &self.domain)
+	}
+
+	/// Returns hash to be signed, `keccak256("\x19\x01" || domainSeparator || hashStruct(message))`.
+	pub fn message(&self) -> Result<Message, Error> {
+		let mut data = vec![0x19, 0x01];
+		data.extend_from_slice(&try!(self.domain_separator()));
+		data.extend_from_slice(&try!(self.hash_struct(&self.primary_type, &self.message)));
+		Ok(Message::from(data.keccak256()))
+	}
+}
+
+impl FromStr for TypedData {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let json = try!(Json::from_str(s).or_else(|_| error("invalid json")));
+
+		let mut types = BTreeMap::new();
+		let json_types = match json.find("types").and_then(Json::as_object) {
+			Some(json_types) => json_types,
+			None => return error("missing types"),
+		};
+
+		for (name, json_members) in json_types {
+			let json_members = match json_members.as_array() {
+				Some(json_members) => json_members,
+				None => return error("invalid type definition"),
+			};
+
+			let mut members = Vec::new();
+			for member in json_members {
+				match (member.find("name").and_then(Json::as_string), member.find("type").and_then(Json::as_string)) {
+					(Some(name), Some(kind)) => members.push(Member {
+						name: name.to_owned(),
+						kind: kind.to_owned(),
+					}),
+					_ => return error("invalid type definition"),
+				}
+			}
+			types.insert(name.clone(), members);
+		}
+
+		if !types.contains_key(DOMAIN_TYPE) {
+			return error("missing EIP712Domain type");
+		}
+
+		let typed_data = TypedData {
+			types: types,
+			primary_type: match json.find("primaryType").and_then(Json::as_string) {
+				Some(primary_type) => primary_type.to_owned(),
+				None => return error("missing primaryType"),
+			},
+			domain: try!(json.find("domain").cloned().ok_or(Error::InvalidTypedData("missing domain".into()))),
+			message: try!(json.find("message").cloned().ok_or(Error::InvalidTypedData("missing message".into()))),
+		};
+
+		try!(typed_data.members(&typed_data.primary_type));
+		Ok(typed_data)
+	}
+}
+
+/// Signs typed data.
+pub fn sign_typed_data(secret: &Secret, typed_data: &TypedData) -> Result<Signature, Error> {
+	sign(secret, &try!(typed_data.message()))
+}
+
+/// Recovers public of the typed data signer.
+pub fn recover_typed_data(signature: &Signature, typed_data: &TypedData) -> Result<Public, Error> {
+	recover(signature, &try!(typed_data.message()))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::ToHex;
+	use rustc_serialize::json::Json;
+	use keccak::Keccak256;
+	use {KeyPair, Secret, public_to_address};
+	use super::{TypedData, sign_typed_data, recover_typed_data, encode_integer};
+
+	const MAIL: &'static str = r#"{
+		"types": {
+			"EIP712Domain": [
+				{ "name": "name", "type": "string" },
+				{ "name": "version", "type": "string" },
+				{ "name": "chainId", "type": "uint256" },
+				{ "name": "verifyingContract", "type": "address" }
+			],
+			"Person": [
+				{ "name": "name", "type": "string" },
+				{ "name": "wallet", "type": "address" }
+			],
+			"Mail": [
+				{ "name": "from", "type": "Person" },
+				{ "name": "to", "type": "Person" },
+				{ "name": "contents", "type": "string" }
+			]
+		},
+		"primaryType": "Mail",
+		"domain": {
+			"name": "Ether Mail",
+			"version": "1",
+			"chainId": 1,
+			"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+		},
+		"message": {
+			"from": {
+				"name": "Cow",
+				"wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+			},
+			"to": {
+				"name": "Bob",
+				"wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"
+			},
+			"contents": "Hello, Bob!"
+		}
+	}"#;
+
+	#[test]
+	fn mail_example() {
+		let typed_data = TypedData::from_str(MAIL).unwrap();
+		assert_eq!(typed_data.encode_type("Mail").unwrap(), "Mail(Person from,Person to,string contents)Person(string name,address wallet)");
+		assert_eq!(typed_data.type_hash("Mail").unwrap().to_hex(), "a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2");
+		assert_eq!(typed_data.hash_struct("Mail", &typed_data.message).unwrap().to_hex(), "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e");
+		assert_eq!(typed_data.domain_separator().unwrap().to_hex(), "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f");
+		assert_eq!(typed_data.message().unwrap().to_hex(), "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2");
+	}
+
+	#[test]
+	fn sign_and_recover_mail() {
+		let typed_data = TypedData::from_str(MAIL).unwrap();
+		let keypair = KeyPair::from_secret(Secret::from(b"cow".keccak256())).unwrap();
+		assert_eq!(keypair.address().to_hex(), "cd2a3d9f938e13cd947ec05abc7fe734df8dd826");
+
+		let signature = sign_typed_data(keypair.secret(), &typed_data).unwrap();
+		assert_eq!(signature.r().to_hex(), "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d");
+		assert_eq!(signature.s().to_hex(), "07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562");
+		assert_eq!(signature.clone().into_electrum()[64], 28);
+
+		let public = recover_typed_data(&signature, &typed_data).unwrap();
+		assert_eq!(public_to_address(&public), keypair.address());
+	}
+
+	#[test]
+	fn encode_type_with_arrays() {
+		let json = r#"{
+			"types": {
+				"EIP712Domain": [{ "name": "name", "type": "string" }],
+				"Person": [{ "name": "name", "type": "string" }, { "name": "wallets", "type": "address[]" }],
+				"Group": [{ "name": "name", "type": "string" }, { "name": "members", "type": "Person[]" }],
+				"Mail": [{ "name": "from", "type": "Person" }, { "name": "to", "type": "Group[2]" }, { "name": "contents", "type": "string" }]
+			},
+			"primaryType": "Mail",
+			"domain": { "name": "Ether Mail" },
+			"message": {
+				"from": { "name": "Cow", "wallets": ["0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"] },
+				"to": [{ "name": "A", "members": [] }, { "name": "B", "members": [] }],
+				"contents": "Hello"
+			}
+		}"#;
+
+		let typed_data = TypedData::from_str(json).unwrap();
+		assert_eq!(typed_data.encode_type("Mail").unwrap(), "Mail(Person from,Group[2] to,string contents)Group(string name,Person[] members)Person(string name,address[] wallets)");
+		assert!(typed_data.message().is_ok());
+	}
+
+	#[test]
+	fn invalid_typed_data() {
+		assert!(TypedData::from_str("{}").is_err());
+		let json = MAIL.replace(r#""contents": "Hello, Bob!""#, r#""contents": 5"#);
+		assert!(TypedData::from_str(&json).unwrap().message().is_err());
+		let json = MAIL.replace(r#""primaryType": "Mail""#, r#""primaryType": "Letter""#);
+		assert!(TypedData::from_str(&json).is_err());
+	}
+
+	#[test]
+	fn encode_integers() {
+		assert_eq!(encode_integer(&Json::U64(1), "uint256").unwrap()[31], 1);
+		assert_eq!(encode_integer(&Json::String("0x0100".into()), "uint256").unwrap()[30], 1);
+		assert_eq!(encode_integer(&Json::String("-1".into()), "int256").unwrap(), [0xffu8; 32]);
+		assert!(encode_integer(&Json::String("-1".into()), "uint256").is_err());
+		let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+		assert_eq!(encode_integer(&Json::String(max.into()), "uint").unwrap(), [0xffu8; 32]);
+		let overflow = "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+		assert!(encode_integer(&Json::String(overflow.into()), "uint256").is_err());
+	}
+
+	#[test]
+	fn encode_integers_in_range() {
+		assert_eq!(encode_integer(&Json::U64(255), "uint8").unwrap()[31], 0xff);
+		assert!(encode_integer(&Json::U64(300), "uint8").is_err());
+		assert!(encode_integer(&Json::String("0x100".into()), "uint8").is_err());
+		assert_eq!(encode_integer(&Json::I64(127), "int8").unwrap()[31], 0x7f);
+		assert!(encode_integer(&Json::I64(128), "int8").is_err());
+		assert_eq!(encode_integer(&Json::I64(-128), "int8").unwrap()[31], 0x80);
+		assert_eq!(encode_integer(&Json::I64(-128), "int8").unwrap()[0], 0xff);
+		assert!(encode_integer(&Json::I64(-129), "int8").is_err());
+		let min = "-57896044618658097711785492504343953926634992332820282019728792003956564819968";
+		assert_eq!(encode_integer(&Json::String(min.into()), "int256").unwrap()[0], 0x80);
+		assert!(encode_integer(&Json::String(min[1..].into()), "int256").is_err());
+		assert!(encode_integer(&Json::U64(1), "uint7").is_err());
+		assert!(encode_integer(&Json::U64(1), "int264").is_err());
+	}
+}
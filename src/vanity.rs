@@ -0,0 +1,114 @@
+use incremental::run;
+use math::{secret_add, public_add_secret};
+use super::{Incremental, Matcher, Generator, Progress, KeyPair, Secret, Public, Address, Error, public_to_address};
+
+/// Tries to find keypair with address matching the pattern.
+pub struct Vanity {
+	matcher: Matcher,
+	iterations: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl Vanity {
+	pub fn new(matcher: Matcher, iterations: usize) -> Self {
+		Vanity::with_threads(matcher, iterations, 1)
+	}
+
+	pub fn with_threads(matcher: Matcher, iterations: usize, threads: usize) -> Self {
+		Vanity::with_progress(matcher, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(matcher: Matcher, iterations: usize, threads: usize, progress: Progress) -> Self {
+		Vanity {
+			matcher: matcher,
+			iterations: iterations,
+			threads: threads,
+			progress: progress,
+		}
+	}
+}
+
+impl Generator for Vanity {
+	fn generate(self) -> Result<KeyPair, Error> {
+		let matcher = self.matcher;
+		Incremental::with_progress(move |address: &Address| matcher.is_match(address), self.iterations, self.threads, self.progress).generate()
+	}
+}
+
+/// Split-key vanity search. Finds secret offset, which added to requester's secret
+/// gives address matching the pattern, without knowing requester's secret.
+pub struct VanityWork {
+	public: Public,
+	matcher: Matcher,
+	iterations: usize,
+	threads: usize,
+	progress: Progress,
+}
+
+impl VanityWork {
+	/// Creates work for public shared by the requester.
+	pub fn new(public: Public, matcher: Matcher, iterations: usize, threads: usize) -> Self {
+		VanityWork::with_progress(public, matcher, iterations, threads, Progress::new())
+	}
+
+	pub fn with_progress(public: Public, matcher: Matcher, iterations: usize, threads: usize, progress: Progress) -> Self {
+		VanityWork {
+			public: public,
+			matcher: matcher,
+			iterations: iterations,
+			threads: threads,
+			progress: progress,
+		}
+	}
+
+	/// Returns the offset and the address of the final keypair.
+	pub fn search(self) -> Result<(Secret, Address), Error> {
+		let matcher = self.matcher;
+		let offset = try!(run(Some(self.public.clone()), move |address: &Address| matcher.is_match(address), self.iterations, self.threads, &self.progress));
+		let address = try!(vanity_address(&self.public, &offset));
+		Ok((offset, address))
+	}
+}
+
+/// Returns address of the final keypair for requester's public and worker's offset.
+pub fn vanity_address(public: &Public, offset: &Secret) -> Result<Address, Error> {
+	let mut public = public.clone();
+	try!(public_add_secret(&mut public, offset));
+	Ok(public_to_address(&public))
+}
+
+/// Combines requester's secret with worker's offset into the final keypair.
+pub fn vanity_combine(secret: &Secret, offset: &Secret) -> Result<KeyPair, Error> {
+	let mut secret = secret.clone();
+	try!(secret_add(&mut secret, offset));
+	KeyPair::from_secret(secret)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use {Generator, Random, Matcher, Vanity};
+	use super::{VanityWork, vanity_address, vanity_combine};
+
+	#[test]
+	fn vanity_generator() {
+		let matcher = Matcher::from_str("f...A").unwrap();
+		let keypair = Vanity::with_threads(matcher.clone(), usize::max_value(), 2).generate().unwrap();
+		assert!(matcher.is_match(&keypair.address()));
+		assert!(keypair.address().to_checksum().starts_with('f'));
+		assert!(keypair.address().to_checksum().ends_with('A'));
+	}
+
+	#[test]
+	fn split_key_vanity() {
+		let request = Random.generate().unwrap();
+		let matcher = Matcher::from_str("ab").unwrap();
+		let (offset, address) = VanityWork::new(request.public().clone(), matcher.clone(), usize::max_value(), 2).search().unwrap();
+		assert!(matcher.is_match(&address));
+		assert_eq!(vanity_address(request.public(), &offset).unwrap(), address);
+
+		let keypair = vanity_combine(request.secret(), &offset).unwrap();
+		assert_eq!(keypair.address(), address);
+	}
+}